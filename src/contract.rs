@@ -1,22 +1,107 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use cw2::set_contract_version;
+use cosmwasm_std::{
+    to_binary, Addr, Api, Binary, BlockInfo, Coin, Decimal, Deps, DepsMut, Env, MessageInfo, Reply,
+    Response, StdResult, Storage, Uint128,
+};
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{NATIVE_DENOM, TAKERADDRESS, TAKERFEE};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SaleSimulationInfo};
+use crate::state::{Collection, Config, CONFIG, PAUSE};
 
 use self::execute::{
-    admin_remove_sale, buy, register_collection, remove_sale, update_collection, update_ownership,
-    update_sale, update_taker_fee,
+    accept_collection_offer, add_hook, admin_remove_sale, approve_collection, block_address,
+    block_token, buy, cancel_order_nonces, cancel_sealed_auction, cleanup_deregistered_collection,
+    close_launchpad, commit_sealed_bid, contribute_to_group_buy, create_collection_offer,
+    create_group_buy, create_launchpad, deregister_collection, fill_signed_order, grant_role,
+    hook_failed, increase_min_nonce, mint, pause_marketplace, receive_nft, register_collection,
+    reject_collection, remove_collection_offer, remove_hook, remove_sale, reveal_sealed_bid,
+    revoke_role, settle_group_buy, settle_sealed_auction, submit_collection, unblock_address,
+    unblock_token, unpause_marketplace, update_allowed_code_ids, update_collection,
+    update_collection_metadata, update_collection_pause, update_collection_royalty,
+    update_launchpad_allowlist, update_merkle_allowlist, update_ownership, update_pull_payments,
+    update_registration_bond, update_sale, update_taker_fee, verify_transfer, withdraw,
+    withdraw_group_buy_contribution,
+};
+use self::migration::run_migrations;
+use self::query::{
+    get_allowed_code_ids, get_balances, get_blocked_addresses, get_blocked_tokens, get_collection,
+    get_collection_metadata, get_collection_offers, get_collections, get_config,
+    get_deregistration, get_group_buy, get_group_buy_contribution, get_hooks, get_last_trade,
+    get_launchpad, get_launchpad_mints, get_merkle_claims, get_order_nonce,
+    get_pending_collections, get_role_members, get_roles, get_sale, get_sales, get_sealed_auction,
+    get_sealed_bid, get_taker_fee, search_collections, simulate_sale,
 };
-use self::query::{get_collection, get_sale, get_taker_fee};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:cw721-marketplace";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+pub const MAX_ROYALTY_PERCENTAGE: u64 = 90;
+pub const MAX_METADATA_NAME_LENGTH: usize = 64;
+pub const MAX_METADATA_DESCRIPTION_LENGTH: usize = 1024;
+pub const MAX_METADATA_LINKS: usize = 10;
+/// Seconds a royalty increase waits before it applies to sales
+pub const ROYALTY_INCREASE_DELAY: u64 = 7 * 24 * 60 * 60;
+/// Bids a sealed auction accepts, bounding the refunds made at settlement
+pub const MAX_SEALED_BIDS: u32 = 100;
+/// Entries `CleanupDeregisteredCollection` removes per call by default, and at most
+pub const DEFAULT_CLEANUP_LIMIT: u32 = 30;
+pub const MAX_CLEANUP_LIMIT: u32 = 100;
+/// Reply id of hooks registered with `ignore_errors`, above any transfer id
+pub const HOOK_REPLY_ID: u64 = u64::MAX;
+
+/// Validates a user supplied address, keeping the rejected input in the error.
+pub fn validate_address(api: &dyn Api, address: &str) -> Result<Addr, ContractError> {
+    api.addr_validate(address)
+        .map_err(|_| ContractError::InvalidAddress {
+            address: address.to_string(),
+        })
+}
+
+/// Whether the marketplace-wide pause is in effect at `block`.
+pub fn is_paused(storage: &dyn Storage, block: &BlockInfo) -> StdResult<bool> {
+    Ok(match PAUSE.may_load(storage)? {
+        Some(pause) => pause
+            .expires_at_height
+            .map_or(true, |height| block.height < height),
+        None => false,
+    })
+}
+
+/// Splits a sale `price` between the taker, the collection's royalty recipient and the seller.
+pub fn split_sale_price(
+    config: &Config,
+    collection: &Collection,
+    price: &Coin,
+) -> StdResult<SaleSimulationInfo> {
+    let taker_fee = price.amount * Decimal::percent(config.taker_fee);
+
+    let mut royalty = Uint128::zero();
+    let mut royalty_recipient = None;
+
+    if let Some(royalty_percentage) = collection.royalty_percentage {
+        if let Some(royalty_payment_address) = &collection.royalty_payment_address {
+            royalty = price.amount * Decimal::percent(royalty_percentage);
+            if !royalty.is_zero() {
+                royalty_recipient = Some(royalty_payment_address.clone());
+            }
+        }
+    }
+
+    let seller_proceeds = price.amount.checked_sub(taker_fee + royalty)?;
+
+    Ok(SaleSimulationInfo {
+        price: price.clone(),
+        taker_fee,
+        royalty,
+        royalty_recipient,
+        seller_proceeds,
+    })
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -25,11 +110,15 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     cw_ownable::initialize_owner(deps.storage, deps.api, Some(&info.sender.to_string()))?;
-    TAKERFEE.save(deps.storage, &msg.taker_fee.u64())?;
-    NATIVE_DENOM.save(deps.storage, &msg.native_denom)?;
-    TAKERADDRESS.save(
+    CONFIG.save(
         deps.storage,
-        &deps.api.addr_validate(&msg.taker_address).unwrap(),
+        &Config {
+            taker_fee: msg.taker_fee.u64(),
+            taker_address: validate_address(deps.api, &msg.taker_address)?,
+            native_denom: msg.native_denom,
+            registration_bond: msg.registration_bond,
+            pull_payments: msg.pull_payments,
+        },
     )?;
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
@@ -64,6 +153,7 @@ pub fn execute(
             is_paused,
         } => update_collection(
             deps,
+            env,
             info,
             contract_address,
             royalty_percentage,
@@ -75,71 +165,516 @@ pub fn execute(
             token_id,
         } => admin_remove_sale(deps, info, contract_address, token_id),
         ExecuteMsg::UpdateTakerFee { taker_fee } => update_taker_fee(deps, info, taker_fee),
+        ExecuteMsg::UpdateRegistrationBond { registration_bond } => {
+            update_registration_bond(deps, info, registration_bond)
+        }
+        ExecuteMsg::UpdatePullPayments { pull_payments } => {
+            update_pull_payments(deps, info, pull_payments)
+        }
+        ExecuteMsg::ApproveCollection { contract_address } => {
+            approve_collection(deps, info, contract_address)
+        }
+        ExecuteMsg::RejectCollection {
+            contract_address,
+            reason,
+        } => reject_collection(deps, info, contract_address, reason),
+        ExecuteMsg::UpdateCollectionPause {
+            contract_address,
+            is_paused,
+        } => update_collection_pause(deps, info, contract_address, is_paused),
+        ExecuteMsg::DeregisterCollection { contract_address } => {
+            deregister_collection(deps, env, info, contract_address)
+        }
+        ExecuteMsg::CleanupDeregisteredCollection {
+            contract_address,
+            limit,
+        } => cleanup_deregistered_collection(deps, env, contract_address, limit),
+        ExecuteMsg::AddHook {
+            contract_address,
+            ignore_errors,
+        } => add_hook(deps, info, contract_address, ignore_errors),
+        ExecuteMsg::RemoveHook { contract_address } => remove_hook(deps, info, contract_address),
+        ExecuteMsg::UpdateAllowedCodeIds { add, remove } => {
+            update_allowed_code_ids(deps, info, add, remove)
+        }
+        ExecuteMsg::UpdateCollectionMetadata {
+            contract_address,
+            metadata,
+        } => update_collection_metadata(deps, info, contract_address, metadata),
+        ExecuteMsg::GrantRole { role, address } => grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => revoke_role(deps, info, role, address),
+        ExecuteMsg::SubmitCollection {
+            contract_address,
+            royalty_percentage,
+            royalty_payment_address,
+        } => submit_collection(
+            deps,
+            info,
+            contract_address,
+            royalty_percentage,
+            royalty_payment_address,
+        ),
+        ExecuteMsg::UpdateCollectionRoyalty {
+            contract_address,
+            royalty_percentage,
+            royalty_payment_address,
+        } => update_collection_royalty(
+            deps,
+            env,
+            info,
+            contract_address,
+            royalty_percentage,
+            royalty_payment_address,
+        ),
         ExecuteMsg::UpdateSale {
             contract_address,
             token_id,
             price,
-        } => update_sale(deps, env, info, contract_address, token_id, price),
+            starts_at,
+        } => update_sale(
+            deps,
+            env,
+            info,
+            contract_address,
+            token_id,
+            price,
+            starts_at,
+        ),
         ExecuteMsg::RemoveSale {
             contract_address,
             token_id,
         } => remove_sale(deps, info, contract_address, token_id),
+        ExecuteMsg::ReceiveNft(msg) => receive_nft(deps, env, info, msg),
+        ExecuteMsg::CancelOrderNonces { nonces } => cancel_order_nonces(deps, info, nonces),
+        ExecuteMsg::IncreaseMinNonce { min_nonce } => increase_min_nonce(deps, info, min_nonce),
         ExecuteMsg::AcceptCollectionOffer {
-            contract_address: _,
-            token_id: _,
-        } => todo!(),
+            contract_address,
+            token_id,
+            offeror,
+        } => accept_collection_offer(deps, env, info, contract_address, token_id, offeror),
         ExecuteMsg::Buy {
             contract_address,
             token_id,
-        } => buy(deps, info, contract_address, token_id),
+            expected_price,
+            expected_version,
+            merkle_proof,
+        } => buy(
+            deps,
+            env,
+            info,
+            contract_address,
+            token_id,
+            expected_price,
+            expected_version,
+            merkle_proof,
+        ),
         ExecuteMsg::CreateCollectionOffer {
-            contract_address: _,
-            price: _,
-        } => todo!(),
-        ExecuteMsg::RemoveCollectionOffer {
-            contract_address: _,
-        } => todo!(),
+            contract_address,
+            price,
+        } => create_collection_offer(deps, env, info, contract_address, price),
+        ExecuteMsg::RemoveCollectionOffer { contract_address } => {
+            remove_collection_offer(deps, info, contract_address)
+        }
+        ExecuteMsg::FillSignedOrder {
+            order,
+            pubkey,
+            signature,
+        } => fill_signed_order(deps, env, info, order, pubkey, signature),
+        ExecuteMsg::CancelSealedAuction {
+            contract_address,
+            token_id,
+        } => cancel_sealed_auction(deps, info, contract_address, token_id),
+        ExecuteMsg::CommitSealedBid {
+            contract_address,
+            token_id,
+            commitment,
+        } => commit_sealed_bid(deps, env, info, contract_address, token_id, commitment),
+        ExecuteMsg::RevealSealedBid {
+            contract_address,
+            token_id,
+            amount,
+            salt,
+        } => reveal_sealed_bid(deps, env, info, contract_address, token_id, amount, salt),
+        ExecuteMsg::SettleSealedAuction {
+            contract_address,
+            token_id,
+        } => settle_sealed_auction(deps, env, contract_address, token_id),
+        ExecuteMsg::CreateGroupBuy {
+            contract_address,
+            token_id,
+            recipient,
+            deadline,
+        } => create_group_buy(
+            deps,
+            env,
+            info,
+            contract_address,
+            token_id,
+            recipient,
+            deadline,
+        ),
+        ExecuteMsg::ContributeToGroupBuy { group_buy_id } => {
+            contribute_to_group_buy(deps, env, info, group_buy_id)
+        }
+        ExecuteMsg::SettleGroupBuy { group_buy_id } => settle_group_buy(deps, env, group_buy_id),
+        ExecuteMsg::WithdrawGroupBuyContribution { group_buy_id } => {
+            withdraw_group_buy_contribution(deps, env, info, group_buy_id)
+        }
+        ExecuteMsg::CreateLaunchpad {
+            contract_address,
+            terms,
+        } => create_launchpad(deps, env, info, contract_address, terms),
+        ExecuteMsg::UpdateLaunchpadAllowlist {
+            contract_address,
+            phase_id,
+            add,
+            remove,
+        } => update_launchpad_allowlist(deps, info, contract_address, phase_id, add, remove),
+        ExecuteMsg::Mint {
+            contract_address,
+            merkle_proof,
+        } => mint(deps, env, info, contract_address, merkle_proof),
+        ExecuteMsg::UpdateMerkleAllowlist {
+            contract_address,
+            target,
+            allowlist,
+        } => update_merkle_allowlist(deps, info, contract_address, target, allowlist),
+        ExecuteMsg::CloseLaunchpad { contract_address } => {
+            close_launchpad(deps, info, contract_address)
+        }
+        ExecuteMsg::Withdraw { denom } => withdraw(deps, info, denom),
+        ExecuteMsg::PauseMarketplace { expires_at_height } => {
+            pause_marketplace(deps, env, info, expires_at_height)
+        }
+        ExecuteMsg::UnpauseMarketplace {} => unpause_marketplace(deps, info),
+        ExecuteMsg::BlockAddress { address, reason } => {
+            block_address(deps, env, info, address, reason)
+        }
+        ExecuteMsg::UnblockAddress { address } => unblock_address(deps, info, address),
+        ExecuteMsg::BlockToken {
+            contract_address,
+            token_id,
+            reason,
+        } => block_token(deps, env, info, contract_address, token_id, reason),
+        ExecuteMsg::UnblockToken {
+            contract_address,
+            token_id,
+        } => unblock_token(deps, info, contract_address, token_id),
         ExecuteMsg::UpdateOwnership(action) => update_ownership(deps, env, info, action),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetSale {
             contract_address,
             token_id,
         } => to_binary(&get_sale(deps, contract_address, token_id)?),
-        QueryMsg::GetSales { start, limit } => todo!(),
+        QueryMsg::GetSales {
+            start_after,
+            limit,
+            status,
+        } => to_binary(&get_sales(deps, env, start_after, limit, status)?),
         QueryMsg::GetCollection { contract_address } => {
-            to_binary(&get_collection(deps, contract_address)?)
+            to_binary(&get_collection(deps, env, contract_address)?)
+        }
+        QueryMsg::GetCollections { start_after, limit } => {
+            to_binary(&get_collections(deps, env, start_after, limit)?)
         }
-        QueryMsg::GetCollections { start, limit } => todo!(),
         QueryMsg::GetTakerFee {} => to_binary(&get_taker_fee(deps)?),
+        QueryMsg::GetConfig {} => to_binary(&get_config(deps, env)?),
+        QueryMsg::GetBlockedAddresses { start_after, limit } => {
+            to_binary(&get_blocked_addresses(deps, start_after, limit)?)
+        }
+        QueryMsg::GetBlockedTokens {
+            contract_address,
+            start_after,
+            limit,
+        } => to_binary(&get_blocked_tokens(
+            deps,
+            contract_address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetHooks { start_after, limit } => {
+            to_binary(&get_hooks(deps, start_after, limit)?)
+        }
+        QueryMsg::GetAllowedCodeIds { start_after, limit } => {
+            to_binary(&get_allowed_code_ids(deps, start_after, limit)?)
+        }
+        QueryMsg::GetCollectionMetadata { contract_address } => {
+            to_binary(&get_collection_metadata(deps, env, contract_address)?)
+        }
+        QueryMsg::SearchCollections {
+            category,
+            is_verified,
+            start_after,
+            limit,
+        } => to_binary(&search_collections(
+            deps,
+            env,
+            category,
+            is_verified,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetPendingCollections { start_after, limit } => {
+            to_binary(&get_pending_collections(deps, start_after, limit)?)
+        }
+        QueryMsg::GetOrderNonce { seller, nonce } => {
+            to_binary(&get_order_nonce(deps, seller, nonce)?)
+        }
+        QueryMsg::GetBalances { address } => to_binary(&get_balances(deps, address)?),
+        QueryMsg::GetLastTrade {
+            contract_address,
+            token_id,
+        } => to_binary(&get_last_trade(deps, contract_address, token_id)?),
+        QueryMsg::GetSealedAuction {
+            contract_address,
+            token_id,
+        } => to_binary(&get_sealed_auction(deps, contract_address, token_id)?),
+        QueryMsg::GetSealedBid {
+            contract_address,
+            token_id,
+            bidder,
+        } => to_binary(&get_sealed_bid(deps, contract_address, token_id, bidder)?),
+        QueryMsg::GetDeregistration { contract_address } => {
+            to_binary(&get_deregistration(deps, contract_address)?)
+        }
+        QueryMsg::GetMerkleClaims {
+            merkle_root,
+            address,
+        } => to_binary(&get_merkle_claims(deps, merkle_root, address)?),
+        QueryMsg::GetGroupBuy { group_buy_id } => to_binary(&get_group_buy(deps, group_buy_id)?),
+        QueryMsg::GetLaunchpad { contract_address } => {
+            to_binary(&get_launchpad(deps, env, contract_address)?)
+        }
+        QueryMsg::GetLaunchpadMints {
+            contract_address,
+            phase_id,
+            address,
+        } => to_binary(&get_launchpad_mints(
+            deps,
+            contract_address,
+            phase_id,
+            address,
+        )?),
+        QueryMsg::GetGroupBuyContribution {
+            group_buy_id,
+            contributor,
+        } => to_binary(&get_group_buy_contribution(
+            deps,
+            group_buy_id,
+            contributor,
+        )?),
+        QueryMsg::SimulateSale {
+            contract_address,
+            price,
+        } => to_binary(&simulate_sale(deps, env, contract_address, price)?),
+        QueryMsg::GetCollectionOffers {
+            contract_address,
+            start_after,
+            limit,
+        } => to_binary(&get_collection_offers(
+            deps,
+            contract_address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetRoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&get_role_members(deps, role, start_after, limit)?),
+        QueryMsg::GetRoles { address } => to_binary(&get_roles(deps, address)?),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        HOOK_REPLY_ID => Ok(hook_failed(msg)),
+        // any other reply answers a settled token transfer, keyed by its transfer id
+        transfer_id => verify_transfer(deps, env, transfer_id),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidContractName {
+            expected: CONTRACT_NAME.to_string(),
+            actual: stored.contract,
+        });
+    }
+
+    let from: Version = stored.version.parse()?;
+    let to: Version = CONTRACT_VERSION.parse()?;
+
+    if from > to {
+        return Err(ContractError::CannotMigrateDowngrade {
+            from: stored.version,
+            to: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    run_migrations(deps.storage, &from)?;
+
+    match msg {
+        MigrateMsg::Migrate {
+            taker_fee,
+            taker_address,
+            registration_bond,
+            pull_payments,
+        } => {
+            let mut config = CONFIG.load(deps.storage)?;
+
+            if let Some(taker_fee) = taker_fee {
+                config.taker_fee = taker_fee.u64();
+            }
+
+            if let Some(taker_address) = taker_address {
+                config.taker_address = validate_address(deps.api, &taker_address)?;
+            }
+
+            if let Some(registration_bond) = registration_bond {
+                config.registration_bond = registration_bond;
+            }
+
+            if let Some(pull_payments) = pull_payments {
+                config.pull_payments = pull_payments;
+            }
+
+            CONFIG.save(deps.storage, &config)?;
+        }
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+pub mod migration {
+    use cosmwasm_std::{Addr, StdResult, Storage, Uint128};
+    use cw_storage_plus::Item;
+    use semver::Version;
+
+    use crate::state::{Config, CONFIG};
+
+    /// Runs every storage migration step introduced after `from`, oldest first.
+    pub fn run_migrations(storage: &mut dyn Storage, from: &Version) -> StdResult<()> {
+        if *from < Version::new(0, 2, 0) {
+            v0_2_0(storage)?;
+        }
+
+        Ok(())
+    }
+
+    /// 0.2.0 merges the separate taker fee, taker address and native denom items into `CONFIG`,
+    /// with collection submissions free until a registration bond is configured and proceeds
+    /// sent on sale as before.
+    pub fn v0_2_0(storage: &mut dyn Storage) -> StdResult<()> {
+        const TAKERFEE: Item<u64> = Item::new("taker_fee");
+        const TAKERADDRESS: Item<Addr> = Item::new("taker_address");
+        const NATIVE_DENOM: Item<String> = Item::new("native_denom");
+
+        CONFIG.save(
+            storage,
+            &Config {
+                taker_fee: TAKERFEE.load(storage)?,
+                taker_address: TAKERADDRESS.load(storage)?,
+                native_denom: NATIVE_DENOM.load(storage)?,
+                registration_bond: Uint128::zero(),
+                pull_payments: false,
+            },
+        )?;
+
+        TAKERFEE.remove(storage);
+        TAKERADDRESS.remove(storage);
+        NATIVE_DENOM.remove(storage);
+
+        Ok(())
+    }
 }
 
 pub mod execute {
     use std::marker::PhantomData;
 
+    use bech32::FromBase32;
     use cosmwasm_std::{
-        coins, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, Empty, Env, Event, MessageInfo,
-        Response, Uint128, Uint64,
+        coins, from_binary, to_vec, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps,
+        DepsMut, Empty, Env, Event, MessageInfo, Order, Reply, Response, StdResult, Storage,
+        SubMsg, SubMsgResult, Timestamp, Uint128, Uint64,
     };
+    use cw721::Cw721ReceiveMsg;
     use cw721_rewards::{helpers::Cw721Contract, ExecuteMsg};
+    use ripemd::Ripemd160;
+    use sha2::{Digest, Sha256};
 
+    use super::{
+        is_paused, split_sale_price, validate_address, DEFAULT_CLEANUP_LIMIT, HOOK_REPLY_ID,
+        MAX_CLEANUP_LIMIT, MAX_METADATA_DESCRIPTION_LENGTH, MAX_METADATA_LINKS,
+        MAX_METADATA_NAME_LENGTH, MAX_ROYALTY_PERCENTAGE, MAX_SEALED_BIDS, ROYALTY_INCREASE_DELAY,
+    };
     use crate::{
-        state::{Collection, Sale, COLLECTIONS, NATIVE_DENOM, SALES, TAKERADDRESS, TAKERFEE},
+        events::{
+            AddressBlocked, AddressUnblocked, CollectionPauseUpdated, CollectionRegistered,
+            ConfigUpdated, ListingCancelled, ListingCreated, ListingSold, ListingUpdated,
+            MarketplaceEvent, MarketplacePaused, MarketplaceUnpaused, OfferAccepted,
+            OfferCancelled, OfferCreated, OwnershipUpdated, SaleSource, TokenBlocked,
+            TokenUnblocked,
+        },
+        msg::{
+            LaunchpadTerms, MarketplaceHookMsg, MerkleAllowlistTarget, MerkleProof, OrderSignDoc,
+            ReceiveNftMsg, SealedBidCommitment, SignedOrder,
+        },
+        state::{
+            AuctionPricing, BlockEntry, Collection, CollectionMetadata, Config, Deregistration,
+            GroupBuy, Hook, Launchpad, MerkleAllowlist, MintPhase, Offer, Pause, PendingCollection,
+            PendingRoyalty, PendingTransfer, RevealedBid, Role, Sale, SealedAuction,
+            SealedAuctionTerms, SealedBid, Trade, ALLOWED_CODE_IDS, BALANCES, BLOCKED_ADDRESSES,
+            BLOCKED_TOKENS, COLLECTIONS, COLLECTION_METADATA, COLLECTION_OFFERS, CONFIG,
+            DEREGISTRATIONS, GROUP_BUYS, GROUP_BUY_CONTRIBUTIONS, HOOKS, LAUNCHPADS,
+            LAUNCHPAD_ALLOWLISTS, LAUNCHPAD_MINTS, LISTING_VERSION, MERKLE_CLAIMS, MIN_NONCES,
+            NEXT_GROUP_BUY_ID, NEXT_TRANSFER_ID, PAUSE, PENDING_COLLECTIONS, PENDING_TRANSFERS,
+            ROLES, SALES, SEALED_AUCTIONS, SEALED_BIDS, TRADES, USED_NONCES,
+        },
         ContractError,
     };
 
+    /// Passes for the contract owner and for any address holding `role`.
+    pub fn assert_owner_or_role(
+        storage: &dyn Storage,
+        sender: &Addr,
+        role: Role,
+    ) -> Result<(), ContractError> {
+        if ROLES.has(storage, (role.as_str(), sender.clone())) {
+            return Ok(());
+        }
+
+        cw_ownable::assert_owner(storage, sender)?;
+
+        Ok(())
+    }
+
+    fn assert_royalty_percentage(royalty_percentage: Option<u64>) -> Result<(), ContractError> {
+        match royalty_percentage {
+            Some(percentage) if percentage > MAX_ROYALTY_PERCENTAGE => {
+                Err(ContractError::RoyaltyTooHigh {
+                    percentage,
+                    max: MAX_ROYALTY_PERCENTAGE,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
     pub fn register_collection(
         deps: DepsMut,
         info: MessageInfo,
@@ -147,7 +682,7 @@ pub mod execute {
         royalty_percentage: Option<u64>,
         royalty_payment_address: Option<String>,
     ) -> Result<Response, ContractError> {
-        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        assert_owner_or_role(deps.storage, &info.sender, Role::CollectionCurator)?;
 
         let contract_address = deps.api.addr_validate(&contract_address)?;
 
@@ -157,350 +692,8810 @@ pub mod execute {
             return Err(ContractError::CollectionAlreadyRegistered {});
         }
 
-        if royalty_percentage.is_some() {
-            let percentage = royalty_percentage.unwrap();
+        assert_royalty_percentage(royalty_percentage)?;
 
-            assert!(percentage <= 90, "Royalty is higher than 90%");
-        }
+        let validated_royalty_payment_address = royalty_payment_address
+            .as_ref()
+            .map(|address| validate_address(deps.api, address))
+            .transpose()?;
+
+        let (code_id, name) = query_cw721_info(deps.as_ref(), &contract_address)?;
 
         COLLECTIONS.save(
             deps.storage,
             contract_address.clone(),
             &Collection {
                 royalty_percentage,
-                royalty_payment_address: if let Some(royalty_payment_address) =
-                    royalty_payment_address.clone()
-                {
-                    Some(deps.api.addr_validate(&royalty_payment_address).unwrap())
-                } else {
-                    None
-                },
+                royalty_payment_address: validated_royalty_payment_address.clone(),
                 is_paused: false,
+                pending_royalty: None,
+                is_deregistered: false,
+                code_id: Some(code_id),
+                name: Some(name),
             },
         )?;
 
-        Ok(Response::new().add_event(
-            Event::new("register_collection")
-                .add_attribute("contract_address", contract_address)
-                .add_attribute(
-                    "royalty_percentage",
-                    match royalty_percentage {
-                        Some(royalty_percentage) => Uint64::from(royalty_percentage).to_string(),
-                        None => "null".to_string(),
-                    },
-                )
-                .add_attribute(
-                    "royalty_payment_address",
-                    match royalty_payment_address {
-                        Some(royalty_payment_address) => royalty_payment_address,
-                        None => "null".to_string(),
-                    },
-                ),
-        ))
-    }
+        // registering directly supersedes a submission, whose bond goes back to the submitter
+        let mut messages: Vec<CosmosMsg> = Vec::new();
 
-    pub fn update_taker_fee(
-        deps: DepsMut,
-        info: MessageInfo,
-        taker_fee: Uint64,
-    ) -> Result<Response, ContractError> {
-        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        if let Some(pending) =
+            PENDING_COLLECTIONS.may_load(deps.storage, contract_address.clone())?
+        {
+            PENDING_COLLECTIONS.remove(deps.storage, contract_address.clone());
 
-        TAKERFEE.save(deps.storage, &taker_fee.u64())?;
+            if let Some(bond) = pending.bond {
+                messages.push(
+                    BankMsg::Send {
+                        to_address: pending.submitter.to_string(),
+                        amount: vec![bond],
+                    }
+                    .into(),
+                );
+            }
+        }
 
-        Ok(Response::new().add_event(
-            Event::new("update_taker_fee")
-                .add_attribute("taker_fee", Uint64::from(taker_fee).to_string()),
+        Ok(Response::new().add_messages(messages).add_event(
+            CollectionRegistered {
+                contract_address,
+                royalty_percentage,
+                royalty_payment_address: validated_royalty_payment_address,
+            }
+            .to_event(),
         ))
     }
 
-    pub fn admin_remove_sale(
-        deps: DepsMut,
-        info: MessageInfo,
-        contract_address: String,
-        token_id: String,
-    ) -> Result<Response, ContractError> {
-        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    /// Checks that `contract_address` is a cw721 contract of an allowed code ID, returning its
+    /// code ID and collection name.
+    fn query_cw721_info(
+        deps: Deps,
+        contract_address: &Addr,
+    ) -> Result<(u64, String), ContractError> {
+        let not_cw721 = |_| ContractError::NotCw721Contract {
+            address: contract_address.to_string(),
+        };
 
-        let contract_address = deps.api.addr_validate(&contract_address)?;
-        let sale = SALES.load(deps.storage, (contract_address.clone(), token_id.clone()));
+        let code_id = deps
+            .querier
+            .query_wasm_contract_info(contract_address)
+            .map_err(not_cw721)?
+            .code_id;
 
-        if sale.is_err() {
-            return Err(ContractError::SaleDoesNotExist {});
+        if !ALLOWED_CODE_IDS.is_empty(deps.storage) && !ALLOWED_CODE_IDS.has(deps.storage, code_id)
+        {
+            return Err(ContractError::CodeIdNotAllowed { code_id });
         }
 
-        SALES.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+        let cw721 =
+            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData);
+        let contract_info = cw721.contract_info(&deps.querier).map_err(not_cw721)?;
+        cw721.num_tokens(&deps.querier).map_err(not_cw721)?;
 
-        Ok(Response::new().add_event(
-            Event::new("remove_sale")
-                .add_attribute("contract_address", contract_address.to_string())
-                .add_attribute("token_id", token_id),
-        ))
+        Ok((code_id, contract_info.name))
     }
 
-    pub fn update_collection(
+    pub fn add_hook(
         deps: DepsMut,
         info: MessageInfo,
         contract_address: String,
-        royalty_percentage: Option<u64>,
-        royalty_payment_address: Option<String>,
-        is_paused: bool,
+        ignore_errors: bool,
     ) -> Result<Response, ContractError> {
         cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-        let contract_address = deps.api.addr_validate(&contract_address)?;
-
-        let collection = COLLECTIONS.load(deps.storage, contract_address.clone());
+        let contract_address = validate_address(deps.api, &contract_address)?;
 
-        if collection.is_err() {
-            return Err(ContractError::CollectionAlreadyRegistered {});
+        if HOOKS.has(deps.storage, contract_address.clone()) {
+            return Err(ContractError::HookAlreadyRegistered {
+                contract_address: contract_address.to_string(),
+            });
         }
 
-        COLLECTIONS.save(
+        HOOKS.save(
             deps.storage,
             contract_address.clone(),
-            &Collection {
-                royalty_percentage: royalty_percentage,
-                royalty_payment_address: if let Some(royalty_payment_address) =
-                    royalty_payment_address.clone()
-                {
-                    Some(deps.api.addr_validate(&royalty_payment_address).unwrap())
-                } else {
-                    None
-                },
-                is_paused,
-            },
+            &Hook { ignore_errors },
         )?;
 
         Ok(Response::new().add_event(
-            Event::new("update_collection")
+            Event::new("add_hook")
                 .add_attribute("contract_address", contract_address)
-                .add_attribute(
-                    "royalty_percentage",
-                    match royalty_percentage {
-                        Some(royalty_percentage) => Uint64::from(royalty_percentage).to_string(),
-                        None => "null".to_string(),
-                    },
-                )
-                .add_attribute(
-                    "royalty_payment_address",
-                    match royalty_payment_address {
-                        Some(royalty_payment_address) => royalty_payment_address,
-                        None => "null".to_string(),
-                    },
-                )
-                .add_attribute("is_paused", is_paused.to_string()),
+                .add_attribute("ignore_errors", ignore_errors.to_string()),
         ))
     }
 
-    pub fn update_sale(
+    pub fn remove_hook(
         deps: DepsMut,
-        env: Env,
         info: MessageInfo,
         contract_address: String,
-        token_id: String,
-        price: Coin,
     ) -> Result<Response, ContractError> {
-        // check owner
-        let contract_address = deps.api.addr_validate(&contract_address)?;
-        let owner =
-            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
-                .owner_of(&deps.querier, token_id.clone(), false)?;
-
-        if owner.owner != info.sender.to_string() {
-            return Err(ContractError::Unauthorized {});
-        }
-
-        // check approval
-
-        let approval =
-            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
-                .approval(
-                    &deps.querier,
-                    token_id.clone(),
-                    env.contract.address.to_string(),
-                    Some(false),
-                );
-
-        if approval.is_err() {
-            return Err(ContractError::NotApproved {});
-        }
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
 
-        let native_denom = NATIVE_DENOM.load(deps.storage)?;
+        let contract_address = validate_address(deps.api, &contract_address)?;
 
-        if price.denom != native_denom {
-            return Err(ContractError::DenomNotSupported {});
+        if !HOOKS.has(deps.storage, contract_address.clone()) {
+            return Err(ContractError::HookNotRegistered {
+                contract_address: contract_address.to_string(),
+            });
         }
 
-        SALES.save(
-            deps.storage,
-            (contract_address.clone(), token_id.clone()),
-            &Sale {
-                owner_address: info.sender,
-                price: price.clone(),
-            },
-        )?;
+        HOOKS.remove(deps.storage, contract_address.clone());
 
-        // check approval
         Ok(Response::new().add_event(
-            Event::new("update_sale")
-                .add_attribute("contract_address", contract_address)
-                .add_attribute("token_id", token_id)
-                .add_attribute("price", price.amount),
+            Event::new("remove_hook").add_attribute("contract_address", contract_address),
         ))
     }
 
-    pub fn remove_sale(
+    /// Addresses `msg` to every hook. Hooks that ignore errors reply on failure, so the
+    /// marketplace action goes through without them.
+    fn prepare_hooks(storage: &dyn Storage, msg: MarketplaceHookMsg) -> StdResult<Vec<SubMsg>> {
+        HOOKS
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (contract_address, hook) = item?;
+                let notification = msg.clone().into_cosmos_msg(contract_address)?;
+
+                Ok(if hook.ignore_errors {
+                    SubMsg::reply_on_error(notification, HOOK_REPLY_ID)
+                } else {
+                    SubMsg::new(notification)
+                })
+            })
+            .collect()
+    }
+
+    /// Notifies hooks of a settled sale, as an accepted offer if it filled one.
+    fn sale_hooks(storage: &dyn Storage, sold: &ListingSold) -> StdResult<Vec<SubMsg>> {
+        let msg = match sold.source {
+            SaleSource::CollectionOffer => MarketplaceHookMsg::OfferAccepted {
+                contract_address: sold.contract_address.to_string(),
+                token_id: sold.token_id.clone(),
+                seller: sold.seller.to_string(),
+                offeror: sold.buyer.to_string(),
+                price: sold.price.clone(),
+            },
+            SaleSource::Listing | SaleSource::SignedOrder | SaleSource::SealedAuction => {
+                MarketplaceHookMsg::Sold {
+                    contract_address: sold.contract_address.to_string(),
+                    token_id: sold.token_id.clone(),
+                    seller: sold.seller.to_string(),
+                    buyer: sold.buyer.to_string(),
+                    price: sold.price.clone(),
+                }
+            }
+        };
+
+        prepare_hooks(storage, msg)
+    }
+
+    /// Removes a listing made obsolete by the token being sold or escrowed some other way,
+    /// notifying hooks that it was delisted.
+    fn remove_stale_sale(
+        storage: &mut dyn Storage,
+        contract_address: &Addr,
+        token_id: &str,
+    ) -> StdResult<Vec<SubMsg>> {
+        let key = (contract_address.clone(), token_id.to_string());
+
+        let sale = match SALES.may_load(storage, key.clone())? {
+            Some(sale) => sale,
+            None => return Ok(vec![]),
+        };
+
+        SALES.remove(storage, key);
+
+        prepare_hooks(
+            storage,
+            MarketplaceHookMsg::Delisted {
+                contract_address: contract_address.to_string(),
+                token_id: token_id.to_string(),
+                seller: sale.owner_address.to_string(),
+            },
+        )
+    }
+
+    /// Records a failed hook that was registered to ignore errors.
+    pub fn hook_failed(msg: Reply) -> Response {
+        let error = match msg.result {
+            SubMsgResult::Err(error) => error,
+            SubMsgResult::Ok(_) => String::new(),
+        };
+
+        Response::new().add_event(Event::new("hook_failed").add_attribute("error", error))
+    }
+
+    pub fn update_allowed_code_ids(
+        deps: DepsMut,
+        info: MessageInfo,
+        add: Vec<u64>,
+        remove: Vec<u64>,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::CollectionCurator)?;
+
+        for code_id in &add {
+            ALLOWED_CODE_IDS.save(deps.storage, *code_id, &Empty {})?;
+        }
+
+        for code_id in &remove {
+            ALLOWED_CODE_IDS.remove(deps.storage, *code_id);
+        }
+
+        let join = |code_ids: &[u64]| {
+            code_ids
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        Ok(Response::new().add_event(
+            Event::new("update_allowed_code_ids")
+                .add_attribute("added", join(&add))
+                .add_attribute("removed", join(&remove))
+                .add_attribute("updated_by", info.sender),
+        ))
+    }
+
+    pub fn update_taker_fee(
+        deps: DepsMut,
+        info: MessageInfo,
+        taker_fee: Uint64,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::FeeAdmin)?;
+
+        CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+            config.taker_fee = taker_fee.u64();
+            Ok(config)
+        })?;
+
+        Ok(Response::new().add_event(
+            ConfigUpdated {
+                updated_by: info.sender,
+                taker_fee: Some(taker_fee.u64()),
+                registration_bond: None,
+                pull_payments: None,
+            }
+            .to_event(),
+        ))
+    }
+
+    pub fn admin_remove_sale(
         deps: DepsMut,
         info: MessageInfo,
         contract_address: String,
         token_id: String,
     ) -> Result<Response, ContractError> {
-        // check owner
+        assert_owner_or_role(deps.storage, &info.sender, Role::Moderator)?;
+
         let contract_address = deps.api.addr_validate(&contract_address)?;
-        let owner =
-            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
-                .owner_of(&deps.querier, token_id.clone(), false)?;
+        let sale = SALES
+            .load(deps.storage, (contract_address.clone(), token_id.clone()))
+            .map_err(|_| ContractError::SaleDoesNotExist {})?;
 
-        if owner.owner != info.sender.to_string() {
-            return Err(ContractError::Unauthorized {});
+        SALES.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+
+        let mut messages: Vec<CosmosMsg> = Vec::new();
+
+        if sale.is_escrowed {
+            messages.push(transfer_nft_msg(
+                &contract_address,
+                &token_id,
+                &sale.owner_address,
+            )?);
         }
 
-        SALES.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+        let hooks = prepare_hooks(
+            deps.storage,
+            MarketplaceHookMsg::Delisted {
+                contract_address: contract_address.to_string(),
+                token_id: token_id.clone(),
+                seller: sale.owner_address.to_string(),
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_submessages(hooks)
+            .add_event(
+                ListingCancelled {
+                    contract_address,
+                    token_id,
+                    seller: sale.owner_address,
+                    cancelled_by: info.sender,
+                }
+                .to_event(),
+            ))
+    }
+
+    pub fn update_collection(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        royalty_percentage: Option<u64>,
+        royalty_payment_address: Option<String>,
+        is_paused: Option<bool>,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::CollectionCurator)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+
+        let mut collection = COLLECTIONS
+            .load(deps.storage, contract_address.clone())
+            .map_err(|_| ContractError::CollectionNotExist {})?;
+
+        update_royalty(
+            deps.api,
+            &env,
+            &mut collection,
+            royalty_percentage,
+            royalty_payment_address,
+        )?;
+
+        if let Some(is_paused) = is_paused {
+            collection.is_paused = is_paused;
+        }
+
+        COLLECTIONS.save(deps.storage, contract_address.clone(), &collection)?;
 
-        // check approval
         Ok(Response::new().add_event(
-            Event::new("remove_sale")
-                .add_attribute("contract_address", contract_address.to_string())
-                .add_attribute("token_id", token_id),
+            royalty_event("update_collection", &contract_address, &collection)
+                .add_attribute("is_paused", collection.is_paused.to_string()),
         ))
     }
 
-    pub fn buy(
+    pub fn update_collection_royalty(
         deps: DepsMut,
+        env: Env,
         info: MessageInfo,
         contract_address: String,
-        token_id: String,
+        royalty_percentage: Option<u64>,
+        royalty_payment_address: Option<String>,
     ) -> Result<Response, ContractError> {
         let contract_address = deps.api.addr_validate(&contract_address)?;
-        let sale = SALES.load(deps.storage, (contract_address.clone(), token_id.clone()))?;
 
-        let fund_input = cw_utils::must_pay(&info, &sale.price.denom).unwrap();
+        let mut collection = COLLECTIONS
+            .load(deps.storage, contract_address.clone())
+            .map_err(|_| ContractError::CollectionNotExist {})?;
 
-        if fund_input < sale.price.amount {
-            return Err(ContractError::InsufficientFunds {});
+        if !is_collection_creator(deps.as_ref(), &contract_address, &info.sender)? {
+            return Err(ContractError::NotCollectionCreator {
+                address: info.sender.to_string(),
+            });
         }
 
-        SALES.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+        update_royalty(
+            deps.api,
+            &env,
+            &mut collection,
+            royalty_percentage,
+            royalty_payment_address,
+        )?;
 
-        let taker_fee = TAKERFEE.load(deps.storage)?;
-        let taker_funds = fund_input * Decimal::percent(taker_fee);
+        COLLECTIONS.save(deps.storage, contract_address.clone(), &collection)?;
 
-        let mut messages: Vec<CosmosMsg> = Vec::new();
+        Ok(Response::new().add_event(royalty_event(
+            "update_collection_royalty",
+            &contract_address,
+            &collection,
+        )))
+    }
 
-        if taker_funds.u128() > 0 {
-            let send_taker_funds_msg = BankMsg::Send {
-                to_address: TAKERADDRESS.load(deps.storage).unwrap().to_string(),
-                amount: coins(taker_funds.u128(), &sale.price.denom),
-            };
+    /// Applies a partial royalty update. Decreases take effect at once, while increases are held
+    /// for `ROYALTY_INCREASE_DELAY` so they cannot reprice open listings. No royalty is charged
+    /// without a recipient, so giving a collection its first recipient counts as an increase.
+    fn update_royalty(
+        api: &dyn Api,
+        env: &Env,
+        collection: &mut Collection,
+        royalty_percentage: Option<u64>,
+        royalty_payment_address: Option<String>,
+    ) -> Result<(), ContractError> {
+        assert_royalty_percentage(royalty_percentage)?;
+
+        collection.apply_pending_royalty(env.block.time);
+
+        let royalty_payment_address = royalty_payment_address
+            .map(|address| validate_address(api, &address))
+            .transpose()?;
+
+        let current_royalty = match collection.royalty_payment_address {
+            Some(_) => collection.royalty_percentage.unwrap_or(0),
+            None => 0,
+        };
+        let adds_recipient =
+            collection.royalty_payment_address.is_none() && royalty_payment_address.is_some();
+        let recipient =
+            royalty_payment_address.or_else(|| collection.royalty_payment_address.clone());
+        let percentage = royalty_percentage
+            .or(collection.royalty_percentage)
+            .unwrap_or(0);
+        let new_royalty = match recipient {
+            Some(_) => percentage,
+            None => 0,
+        };
 
-            messages.push(send_taker_funds_msg.into());
+        if new_royalty > current_royalty {
+            collection.pending_royalty = Some(PendingRoyalty {
+                royalty_percentage: percentage,
+                royalty_payment_address: if adds_recipient { recipient } else { None },
+                effective_at: env.block.time.plus_seconds(ROYALTY_INCREASE_DELAY),
+            });
+            if !adds_recipient {
+                collection.royalty_payment_address = recipient;
+            }
+        } else {
+            collection.royalty_payment_address = recipient;
+            if let Some(royalty_percentage) = royalty_percentage {
+                collection.royalty_percentage = Some(royalty_percentage);
+                collection.pending_royalty = None;
+            }
         }
 
-        // royalties
+        Ok(())
+    }
+
+    fn royalty_event(name: &str, contract_address: &Addr, collection: &Collection) -> Event {
+        Event::new(name)
+            .add_attribute("contract_address", contract_address)
+            .add_attribute(
+                "royalty_percentage",
+                match collection.royalty_percentage {
+                    Some(royalty_percentage) => Uint64::from(royalty_percentage).to_string(),
+                    None => "null".to_string(),
+                },
+            )
+            .add_attribute(
+                "royalty_payment_address",
+                match &collection.royalty_payment_address {
+                    Some(royalty_payment_address) => royalty_payment_address.to_string(),
+                    None => "null".to_string(),
+                },
+            )
+            .add_attribute(
+                "pending_royalty_percentage",
+                match &collection.pending_royalty {
+                    Some(pending) => Uint64::from(pending.royalty_percentage).to_string(),
+                    None => "null".to_string(),
+                },
+            )
+            .add_attribute(
+                "pending_royalty_payment_address",
+                match &collection.pending_royalty {
+                    Some(PendingRoyalty {
+                        royalty_payment_address: Some(royalty_payment_address),
+                        ..
+                    }) => royalty_payment_address.to_string(),
+                    _ => "null".to_string(),
+                },
+            )
+            .add_attribute(
+                "pending_royalty_effective_at",
+                match &collection.pending_royalty {
+                    Some(pending) => pending.effective_at.seconds().to_string(),
+                    None => "null".to_string(),
+                },
+            )
+    }
+
+    pub fn update_collection_pause(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+        is_paused: bool,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::Moderator)?;
 
-        let collection = COLLECTIONS.load(deps.storage, contract_address.clone())?;
+        let contract_address = deps.api.addr_validate(&contract_address)?;
 
-        let mut royalty_funds = Uint128::from(0u128);
+        let mut collection = COLLECTIONS
+            .load(deps.storage, contract_address.clone())
+            .map_err(|_| ContractError::CollectionNotExist {})?;
 
-        if let Some(royalty_percentage) = collection.royalty_percentage {
-            if let Some(royalty_payment_address) = collection.royalty_payment_address {
-                royalty_funds = fund_input * Decimal::percent(royalty_percentage);
-                if royalty_funds.u128() > 0 {
-                    let send_royalty_funds_msg = BankMsg::Send {
-                        to_address: royalty_payment_address.to_string(),
-                        amount: coins(royalty_funds.u128(), &sale.price.denom),
-                    };
+        collection.is_paused = is_paused;
+        COLLECTIONS.save(deps.storage, contract_address.clone(), &collection)?;
 
-                    messages.push(send_royalty_funds_msg.into());
-                }
+        Ok(Response::new().add_event(
+            CollectionPauseUpdated {
+                contract_address,
+                is_paused,
             }
-        }
+            .to_event(),
+        ))
+    }
 
-        let owner_funds = fund_input - taker_funds - royalty_funds;
-        if owner_funds.u128() > 0 {
-            let send_owner_funds_msg = BankMsg::Send {
-                to_address: sale.owner_address.to_string(),
-                amount: coins(owner_funds.u128(), &sale.price.denom),
-            };
+    pub fn update_collection_metadata(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+        metadata: CollectionMetadata,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::CollectionCurator)?;
 
-            messages.push(send_owner_funds_msg.into());
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+
+        if !COLLECTIONS.has(deps.storage, contract_address.clone()) {
+            return Err(ContractError::CollectionNotExist {});
         }
 
-        messages.push(
-            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
-                .call(ExecuteMsg::<Empty>::TransferNft {
-                    recipient: info.sender.to_string(),
-                    token_id: token_id.clone(),
-                })?,
-        );
+        validate_metadata(&metadata)?;
 
-        Ok(Response::new().add_messages(messages).add_event(
-            Event::new("buy")
+        COLLECTION_METADATA.save(deps.storage, contract_address.clone(), &metadata)?;
+
+        Ok(Response::new().add_event(
+            Event::new("update_collection_metadata")
                 .add_attribute("contract_address", contract_address)
-                .add_attribute("token_id", token_id)
-                .add_attribute("price", sale.price.amount)
-                .add_attribute("from", sale.owner_address)
-                .add_attribute("to", info.sender.to_string()),
+                .add_attribute("name", metadata.name)
+                .add_attribute(
+                    "category",
+                    metadata.category.unwrap_or_else(|| "null".to_string()),
+                )
+                .add_attribute("is_verified", metadata.is_verified.to_string())
+                .add_attribute("updated_by", info.sender),
         ))
     }
 
-    pub fn update_ownership(
+    fn validate_metadata(metadata: &CollectionMetadata) -> Result<(), ContractError> {
+        let invalid = |reason: &str| ContractError::InvalidCollectionMetadata {
+            reason: reason.to_string(),
+        };
+
+        if metadata.name.trim().is_empty() {
+            return Err(invalid("name is empty"));
+        }
+
+        if metadata.name.len() > MAX_METADATA_NAME_LENGTH {
+            return Err(invalid("name is too long"));
+        }
+
+        if metadata.description.as_ref().map_or(false, |description| {
+            description.len() > MAX_METADATA_DESCRIPTION_LENGTH
+        }) {
+            return Err(invalid("description is too long"));
+        }
+
+        if metadata.external_links.len() > MAX_METADATA_LINKS {
+            return Err(invalid("too many external links"));
+        }
+
+        Ok(())
+    }
+
+    pub fn grant_role(
+        deps: DepsMut,
+        info: MessageInfo,
+        role: Role,
+        address: String,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        let address = validate_address(deps.api, &address)?;
+
+        ROLES.save(deps.storage, (role.as_str(), address.clone()), &Empty {})?;
+
+        Ok(Response::new().add_event(
+            Event::new("grant_role")
+                .add_attribute("role", role.as_str())
+                .add_attribute("address", address),
+        ))
+    }
+
+    pub fn revoke_role(
+        deps: DepsMut,
+        info: MessageInfo,
+        role: Role,
+        address: String,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        let address = validate_address(deps.api, &address)?;
+
+        if !ROLES.has(deps.storage, (role.as_str(), address.clone())) {
+            return Err(ContractError::RoleNotGranted {
+                role: role.as_str().to_string(),
+                address: address.to_string(),
+            });
+        }
+
+        ROLES.remove(deps.storage, (role.as_str(), address.clone()));
+
+        Ok(Response::new().add_event(
+            Event::new("revoke_role")
+                .add_attribute("role", role.as_str())
+                .add_attribute("address", address),
+        ))
+    }
+
+    pub fn block_address(
         deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        action: cw_ownable::Action,
+        address: String,
+        reason: String,
     ) -> Result<Response, ContractError> {
-        let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
-        Ok(Response::new().add_attributes(ownership.into_attributes()))
+        assert_owner_or_role(deps.storage, &info.sender, Role::Moderator)?;
+
+        let address = validate_address(deps.api, &address)?;
+
+        BLOCKED_ADDRESSES.save(
+            deps.storage,
+            address.clone(),
+            &BlockEntry {
+                reason: reason.clone(),
+                blocked_by: info.sender,
+                blocked_at: env.block.time,
+            },
+        )?;
+
+        Ok(Response::new().add_event(AddressBlocked { address, reason }.to_event()))
     }
-}
 
-pub mod query {
-    use cosmwasm_std::{Deps, StdResult, Uint64};
+    pub fn unblock_address(
+        deps: DepsMut,
+        info: MessageInfo,
+        address: String,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::Moderator)?;
 
-    use crate::{
-        msg::{CollectionsInfo, SalesInfo, TakerFeeInfo},
-        state::{COLLECTIONS, SALES, TAKERFEE},
-    };
+        let address = validate_address(deps.api, &address)?;
 
-    pub fn get_sale(
-        deps: Deps,
+        if !BLOCKED_ADDRESSES.has(deps.storage, address.clone()) {
+            return Err(ContractError::NotBlocked {});
+        }
+
+        BLOCKED_ADDRESSES.remove(deps.storage, address.clone());
+
+        Ok(Response::new().add_event(AddressUnblocked { address }.to_event()))
+    }
+
+    pub fn block_token(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
         contract_address: String,
         token_id: String,
-    ) -> StdResult<SalesInfo> {
+        reason: String,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::Moderator)?;
+
         let contract_address = deps.api.addr_validate(&contract_address)?;
-        let sale = SALES.load(deps.storage, (contract_address, token_id))?;
 
-        Ok(SalesInfo { sales: vec![sale] })
+        BLOCKED_TOKENS.save(
+            deps.storage,
+            (contract_address.clone(), token_id.clone()),
+            &BlockEntry {
+                reason: reason.clone(),
+                blocked_by: info.sender,
+                blocked_at: env.block.time,
+            },
+        )?;
+
+        // a blocked token cannot stay listed, and an escrowed one goes back to its seller
+        let mut messages: Vec<CosmosMsg> = Vec::new();
+        let mut hooks: Vec<SubMsg> = Vec::new();
+        let mut events: Vec<Event> = Vec::new();
+
+        // an open sealed auction unwinds, while group buys lose their listing below and
+        // release their contributions
+        if let Some(auction) =
+            SEALED_AUCTIONS.may_load(deps.storage, (contract_address.clone(), token_id.clone()))?
+        {
+            messages.extend(
+                unwind_sealed_auction(deps.storage, &contract_address, &token_id, &auction)?
+                    .into_iter()
+                    .map(|sub_msg| sub_msg.msg),
+            );
+            events.push(
+                Event::new("unwind_sealed_auction")
+                    .add_attribute("contract_address", contract_address.clone())
+                    .add_attribute("token_id", token_id.clone())
+                    .add_attribute("seller", auction.seller),
+            );
+        }
+
+        if let Some(sale) =
+            SALES.may_load(deps.storage, (contract_address.clone(), token_id.clone()))?
+        {
+            SALES.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+
+            if sale.is_escrowed {
+                messages.push(transfer_nft_msg(
+                    &contract_address,
+                    &token_id,
+                    &sale.owner_address,
+                )?);
+            }
+
+            hooks = prepare_hooks(
+                deps.storage,
+                MarketplaceHookMsg::Delisted {
+                    contract_address: contract_address.to_string(),
+                    token_id: token_id.clone(),
+                    seller: sale.owner_address.to_string(),
+                },
+            )?;
+
+            events.push(
+                ListingCancelled {
+                    contract_address: contract_address.clone(),
+                    token_id: token_id.clone(),
+                    seller: sale.owner_address,
+                    cancelled_by: info.sender.clone(),
+                }
+                .to_event(),
+            );
+        }
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_submessages(hooks)
+            .add_events(events)
+            .add_event(
+                TokenBlocked {
+                    contract_address,
+                    token_id,
+                    reason,
+                }
+                .to_event(),
+            ))
     }
 
-    pub fn get_collection(deps: Deps, contract_address: String) -> StdResult<CollectionsInfo> {
+    pub fn unblock_token(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::Moderator)?;
+
         let contract_address = deps.api.addr_validate(&contract_address)?;
-        let collection = COLLECTIONS.load(deps.storage, contract_address)?;
 
-        Ok(CollectionsInfo {
-            collections: vec![collection],
-        })
-    }
+        if !BLOCKED_TOKENS.has(deps.storage, (contract_address.clone(), token_id.clone())) {
+            return Err(ContractError::NotBlocked {});
+        }
 
-    pub fn get_taker_fee(deps: Deps) -> StdResult<TakerFeeInfo> {
-        let taker_fee = TAKERFEE.load(deps.storage)?;
+        BLOCKED_TOKENS.remove(deps.storage, (contract_address.clone(), token_id.clone()));
 
-        Ok(TakerFeeInfo {
-            taker_fee: Uint64::from(taker_fee),
-        })
+        Ok(Response::new().add_event(
+            TokenUnblocked {
+                contract_address,
+                token_id,
+            }
+            .to_event(),
+        ))
     }
-}
-#[cfg(test)]
-mod tests {}
+
+    pub fn submit_collection(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+        royalty_percentage: Option<u64>,
+        royalty_payment_address: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+
+        if COLLECTIONS.has(deps.storage, contract_address.clone()) {
+            return Err(ContractError::CollectionAlreadyRegistered {});
+        }
+
+        assert_royalty_percentage(royalty_percentage)?;
+        query_cw721_info(deps.as_ref(), &contract_address)?;
+
+        let is_creator = is_collection_creator(deps.as_ref(), &contract_address, &info.sender)?;
+
+        // only the collection's creator may set its royalty
+        if (royalty_percentage.is_some() || royalty_payment_address.is_some()) && !is_creator {
+            return Err(ContractError::NotCollectionCreator {
+                address: info.sender.to_string(),
+            });
+        }
+
+        // a submission by anyone else cannot hold the slot against the creator
+        let mut messages: Vec<CosmosMsg> = Vec::new();
+
+        if let Some(previous) =
+            PENDING_COLLECTIONS.may_load(deps.storage, contract_address.clone())?
+        {
+            if !is_creator
+                || is_collection_creator(deps.as_ref(), &contract_address, &previous.submitter)?
+            {
+                return Err(ContractError::CollectionAlreadyPending {});
+            }
+
+            if let Some(bond) = previous.bond {
+                messages.push(
+                    BankMsg::Send {
+                        to_address: previous.submitter.to_string(),
+                        amount: vec![bond],
+                    }
+                    .into(),
+                );
+            }
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+        let bond = if config.registration_bond.is_zero() {
+            cw_utils::nonpayable(&info)?;
+            None
+        } else {
+            let fund_input = cw_utils::must_pay(&info, &config.native_denom)?;
+
+            if fund_input != config.registration_bond {
+                return Err(ContractError::InvalidFunds {
+                    expected: Coin::new(config.registration_bond.u128(), &config.native_denom),
+                    received: Coin::new(fund_input.u128(), &config.native_denom),
+                });
+            }
+
+            Some(Coin::new(fund_input.u128(), &config.native_denom))
+        };
+
+        let royalty_payment_address = royalty_payment_address
+            .map(|address| validate_address(deps.api, &address))
+            .transpose()?;
+
+        PENDING_COLLECTIONS.save(
+            deps.storage,
+            contract_address.clone(),
+            &PendingCollection {
+                submitter: info.sender.clone(),
+                royalty_percentage,
+                royalty_payment_address,
+                bond,
+            },
+        )?;
+
+        Ok(Response::new().add_messages(messages).add_event(
+            Event::new("submit_collection")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("submitter", info.sender),
+        ))
+    }
+
+    pub fn approve_collection(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::CollectionCurator)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let pending = PENDING_COLLECTIONS
+            .load(deps.storage, contract_address.clone())
+            .map_err(|_| ContractError::CollectionNotPending {})?;
+
+        // the allowlist may have changed since the submission
+        let (code_id, name) = query_cw721_info(deps.as_ref(), &contract_address)?;
+
+        PENDING_COLLECTIONS.remove(deps.storage, contract_address.clone());
+        COLLECTIONS.save(
+            deps.storage,
+            contract_address.clone(),
+            &Collection {
+                royalty_percentage: pending.royalty_percentage,
+                royalty_payment_address: pending.royalty_payment_address,
+                is_paused: false,
+                pending_royalty: None,
+                is_deregistered: false,
+                code_id: Some(code_id),
+                name: Some(name),
+            },
+        )?;
+
+        let mut messages: Vec<CosmosMsg> = Vec::new();
+
+        // an approved submission's bond is kept as the registration fee
+        if let Some(bond) = pending.bond {
+            let config = CONFIG.load(deps.storage)?;
+
+            messages.push(
+                BankMsg::Send {
+                    to_address: config.taker_address.to_string(),
+                    amount: vec![bond],
+                }
+                .into(),
+            );
+        }
+
+        Ok(Response::new().add_messages(messages).add_event(
+            Event::new("approve_collection")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("submitter", pending.submitter),
+        ))
+    }
+
+    pub fn reject_collection(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+        reason: String,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::CollectionCurator)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let pending = PENDING_COLLECTIONS
+            .load(deps.storage, contract_address.clone())
+            .map_err(|_| ContractError::CollectionNotPending {})?;
+
+        PENDING_COLLECTIONS.remove(deps.storage, contract_address.clone());
+
+        let mut messages: Vec<CosmosMsg> = Vec::new();
+
+        if let Some(bond) = pending.bond {
+            messages.push(
+                BankMsg::Send {
+                    to_address: pending.submitter.to_string(),
+                    amount: vec![bond],
+                }
+                .into(),
+            );
+        }
+
+        Ok(Response::new().add_messages(messages).add_event(
+            Event::new("reject_collection")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("submitter", pending.submitter)
+                .add_attribute("reason", reason),
+        ))
+    }
+
+    pub fn update_registration_bond(
+        deps: DepsMut,
+        info: MessageInfo,
+        registration_bond: Uint128,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::FeeAdmin)?;
+
+        CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+            config.registration_bond = registration_bond;
+            Ok(config)
+        })?;
+
+        Ok(Response::new().add_event(
+            ConfigUpdated {
+                updated_by: info.sender,
+                taker_fee: None,
+                registration_bond: Some(registration_bond),
+                pull_payments: None,
+            }
+            .to_event(),
+        ))
+    }
+
+    pub fn update_pull_payments(
+        deps: DepsMut,
+        info: MessageInfo,
+        pull_payments: bool,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::FeeAdmin)?;
+
+        CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+            config.pull_payments = pull_payments;
+            Ok(config)
+        })?;
+
+        Ok(Response::new().add_event(
+            ConfigUpdated {
+                updated_by: info.sender,
+                taker_fee: None,
+                registration_bond: None,
+                pull_payments: Some(pull_payments),
+            }
+            .to_event(),
+        ))
+    }
+
+    pub fn update_sale(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        token_id: String,
+        price: Coin,
+        starts_at: Option<Timestamp>,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        // check owner
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        load_active_collection(deps.storage, &env, &contract_address)?;
+
+        let is_escrowed =
+            assert_token_owner(deps.as_ref(), &contract_address, &token_id, &info.sender)?;
+
+        assert_address_not_blocked(deps.storage, &info.sender)?;
+        assert_token_not_blocked(deps.storage, &contract_address, &token_id)?;
+
+        // an escrowed token needs no approval
+        if !is_escrowed {
+            assert_approved(
+                deps.as_ref(),
+                &env,
+                &contract_address,
+                &token_id,
+                &info.sender,
+            )?;
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+
+        if price.denom != config.native_denom {
+            return Err(ContractError::DenomNotSupported {});
+        }
+
+        Ok(save_sale(
+            deps.storage,
+            contract_address,
+            token_id,
+            info.sender,
+            price,
+            is_escrowed,
+            starts_at,
+        )?)
+    }
+
+    pub fn receive_nft(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: Cw721ReceiveMsg,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        // the cw721 contract sending the token is the collection
+        let contract_address = info.sender;
+        load_active_collection(deps.storage, &env, &contract_address)?;
+
+        let seller = deps.api.addr_validate(&msg.sender)?;
+        assert_address_not_blocked(deps.storage, &seller)?;
+        assert_token_not_blocked(deps.storage, &contract_address, &msg.token_id)?;
+
+        match from_binary(&msg.msg)? {
+            ReceiveNftMsg::ListForSale { price, starts_at } => {
+                let config = CONFIG.load(deps.storage)?;
+
+                if price.denom != config.native_denom {
+                    return Err(ContractError::DenomNotSupported {});
+                }
+
+                Ok(save_sale(
+                    deps.storage,
+                    contract_address,
+                    msg.token_id,
+                    seller,
+                    price,
+                    true,
+                    starts_at,
+                )?)
+            }
+            ReceiveNftMsg::StartSealedAuction { terms } => {
+                start_sealed_auction(deps, env, contract_address, msg.token_id, seller, terms)
+            }
+        }
+    }
+
+    /// Saves a listing under a new version, responding with the created or updated event and
+    /// the hook notifications.
+    fn save_sale(
+        storage: &mut dyn Storage,
+        contract_address: Addr,
+        token_id: String,
+        owner_address: Addr,
+        price: Coin,
+        is_escrowed: bool,
+        starts_at: Option<Timestamp>,
+    ) -> StdResult<Response> {
+        let version = LISTING_VERSION.may_load(storage)?.unwrap_or_default() + 1;
+        LISTING_VERSION.save(storage, &version)?;
+
+        let previous = SALES.may_load(storage, (contract_address.clone(), token_id.clone()))?;
+        let is_new = previous.is_none();
+
+        // repricing a private sale keeps it private, a stale listing of a previous owner does
+        // not carry over
+        let merkle_allowlist = previous
+            .filter(|previous| previous.owner_address == owner_address)
+            .and_then(|previous| previous.merkle_allowlist);
+
+        let sale = Sale {
+            owner_address,
+            price,
+            is_escrowed,
+            version,
+            starts_at,
+            merkle_allowlist,
+        };
+
+        SALES.save(storage, (contract_address.clone(), token_id.clone()), &sale)?;
+
+        let hooks = prepare_hooks(
+            storage,
+            MarketplaceHookMsg::Listed {
+                contract_address: contract_address.to_string(),
+                token_id: token_id.clone(),
+                seller: sale.owner_address.to_string(),
+                price: sale.price.clone(),
+            },
+        )?;
+
+        let event = if is_new {
+            ListingCreated {
+                contract_address,
+                token_id,
+                seller: sale.owner_address,
+                price: sale.price,
+                is_escrowed: sale.is_escrowed,
+                version,
+                starts_at: sale.starts_at,
+            }
+            .to_event()
+        } else {
+            ListingUpdated {
+                contract_address,
+                token_id,
+                seller: sale.owner_address,
+                price: sale.price,
+                is_escrowed: sale.is_escrowed,
+                version,
+                starts_at: sale.starts_at,
+            }
+            .to_event()
+        };
+
+        Ok(Response::new().add_submessages(hooks).add_event(event))
+    }
+
+    pub fn remove_sale(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        // check owner
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let is_escrowed =
+            assert_token_owner(deps.as_ref(), &contract_address, &token_id, &info.sender)?;
+
+        SALES.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+
+        let mut messages: Vec<CosmosMsg> = Vec::new();
+
+        // hand an escrowed token back to its seller
+        if is_escrowed {
+            messages.push(transfer_nft_msg(
+                &contract_address,
+                &token_id,
+                &info.sender,
+            )?);
+        }
+
+        let hooks = prepare_hooks(
+            deps.storage,
+            MarketplaceHookMsg::Delisted {
+                contract_address: contract_address.to_string(),
+                token_id: token_id.clone(),
+                seller: info.sender.to_string(),
+            },
+        )?;
+
+        Ok(Response::new()
+            .add_messages(messages)
+            .add_submessages(hooks)
+            .add_event(
+                ListingCancelled {
+                    contract_address,
+                    token_id,
+                    seller: info.sender.clone(),
+                    cancelled_by: info.sender,
+                }
+                .to_event(),
+            ))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn buy(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        token_id: String,
+        expected_price: Option<Coin>,
+        expected_version: Option<u64>,
+        merkle_proof: Option<MerkleProof>,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let sale = SALES
+            .load(deps.storage, (contract_address.clone(), token_id.clone()))
+            .map_err(|_| ContractError::SaleDoesNotExist {})?;
+
+        if let Some(starts_at) = sale.starts_at {
+            if starts_at > env.block.time {
+                return Err(ContractError::ListingNotStarted { starts_at });
+            }
+        }
+
+        // the seller may have repriced the listing after the buyer saw it
+        if let Some(expected) = expected_price {
+            if expected != sale.price {
+                return Err(ContractError::PriceChanged {
+                    expected,
+                    current: sale.price,
+                });
+            }
+        }
+
+        if let Some(expected) = expected_version {
+            if expected != sale.version {
+                return Err(ContractError::ListingVersionChanged {
+                    expected,
+                    current: sale.version,
+                });
+            }
+        }
+        let collection = load_active_collection(deps.storage, &env, &contract_address)?;
+
+        assert_address_not_blocked(deps.storage, &info.sender)?;
+        assert_address_not_blocked(deps.storage, &sale.owner_address)?;
+        assert_token_not_blocked(deps.storage, &contract_address, &token_id)?;
+
+        if let Some(allowlist) = &sale.merkle_allowlist {
+            consume_merkle_allocation(
+                deps.storage,
+                allowlist,
+                &info.sender,
+                merkle_proof.as_ref(),
+            )?;
+        }
+
+        // the seller may have moved the token or revoked the marketplace since listing
+        if !sale.is_escrowed {
+            assert_token_owner(
+                deps.as_ref(),
+                &contract_address,
+                &token_id,
+                &sale.owner_address,
+            )?;
+            assert_approved(
+                deps.as_ref(),
+                &env,
+                &contract_address,
+                &token_id,
+                &sale.owner_address,
+            )?;
+        }
+
+        let fund_input = cw_utils::must_pay(&info, &sale.price.denom)?;
+
+        if fund_input < sale.price.amount {
+            return Err(ContractError::InsufficientFunds {});
+        }
+
+        // the listing price caps what the buyer pays
+        if fund_input > sale.price.amount {
+            return Err(ContractError::InvalidFunds {
+                expected: sale.price.clone(),
+                received: Coin::new(fund_input.u128(), &sale.price.denom),
+            });
+        }
+
+        SALES.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+
+        let (messages, sold) = settle(
+            deps.storage,
+            &contract_address,
+            &collection,
+            &token_id,
+            &sale.price,
+            &sale.owner_address,
+            &info.sender,
+        )?;
+
+        Ok(Response::new()
+            .add_submessages(messages)
+            .add_submessages(sale_hooks(deps.storage, &sold)?)
+            .add_event(sold.to_event()))
+    }
+
+    pub fn create_collection_offer(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        price: Coin,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        load_active_collection(deps.storage, &env, &contract_address)?;
+        assert_address_not_blocked(deps.storage, &info.sender)?;
+
+        let config = CONFIG.load(deps.storage)?;
+
+        if price.denom != config.native_denom {
+            return Err(ContractError::DenomNotSupported {});
+        }
+
+        let fund_input = cw_utils::must_pay(&info, &price.denom)?;
+
+        if fund_input != price.amount {
+            return Err(ContractError::InvalidFunds {
+                expected: price,
+                received: Coin::new(fund_input.u128(), &config.native_denom),
+            });
+        }
+
+        let mut messages: Vec<CosmosMsg> = Vec::new();
+
+        // an offeror holds a single offer per collection, so refund the one being replaced
+        if let Some(previous) = COLLECTION_OFFERS.may_load(
+            deps.storage,
+            (contract_address.clone(), info.sender.clone()),
+        )? {
+            messages.push(
+                BankMsg::Send {
+                    to_address: info.sender.to_string(),
+                    amount: vec![previous.price],
+                }
+                .into(),
+            );
+        }
+
+        COLLECTION_OFFERS.save(
+            deps.storage,
+            (contract_address.clone(), info.sender.clone()),
+            &Offer {
+                offeror_address: info.sender.clone(),
+                price: price.clone(),
+            },
+        )?;
+
+        Ok(Response::new().add_messages(messages).add_event(
+            OfferCreated {
+                contract_address,
+                offeror: info.sender,
+                price,
+            }
+            .to_event(),
+        ))
+    }
+
+    pub fn remove_collection_offer(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+    ) -> Result<Response, ContractError> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let offer = COLLECTION_OFFERS
+            .load(
+                deps.storage,
+                (contract_address.clone(), info.sender.clone()),
+            )
+            .map_err(|_| ContractError::OfferDoesNotExist {})?;
+
+        COLLECTION_OFFERS.remove(
+            deps.storage,
+            (contract_address.clone(), info.sender.clone()),
+        );
+
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: vec![offer.price.clone()],
+            })
+            .add_event(
+                OfferCancelled {
+                    contract_address,
+                    offeror: info.sender,
+                    price: offer.price,
+                }
+                .to_event(),
+            ))
+    }
+
+    pub fn accept_collection_offer(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        token_id: String,
+        offeror: String,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let offeror = deps.api.addr_validate(&offeror)?;
+        let collection = load_active_collection(deps.storage, &env, &contract_address)?;
+        let offer = COLLECTION_OFFERS
+            .load(deps.storage, (contract_address.clone(), offeror.clone()))
+            .map_err(|_| ContractError::OfferDoesNotExist {})?;
+
+        assert_address_not_blocked(deps.storage, &info.sender)?;
+        assert_address_not_blocked(deps.storage, &offeror)?;
+        assert_token_not_blocked(deps.storage, &contract_address, &token_id)?;
+
+        let is_escrowed =
+            assert_token_owner(deps.as_ref(), &contract_address, &token_id, &info.sender)?;
+
+        if !is_escrowed {
+            assert_approved(
+                deps.as_ref(),
+                &env,
+                &contract_address,
+                &token_id,
+                &info.sender,
+            )?;
+        }
+
+        COLLECTION_OFFERS.remove(deps.storage, (contract_address.clone(), offeror.clone()));
+        let delisted_hooks = remove_stale_sale(deps.storage, &contract_address, &token_id)?;
+
+        let (messages, mut sold) = settle(
+            deps.storage,
+            &contract_address,
+            &collection,
+            &token_id,
+            &offer.price,
+            &info.sender,
+            &offeror,
+        )?;
+        sold.source = SaleSource::CollectionOffer;
+
+        Ok(Response::new()
+            .add_submessages(messages)
+            .add_submessages(delisted_hooks)
+            .add_submessages(sale_hooks(deps.storage, &sold)?)
+            .add_event(
+                OfferAccepted {
+                    contract_address,
+                    token_id,
+                    offeror,
+                    seller: info.sender,
+                    price: offer.price,
+                }
+                .to_event(),
+            )
+            .add_event(sold.to_event()))
+    }
+
+    pub fn fill_signed_order(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        order: SignedOrder,
+        pubkey: Binary,
+        signature: Binary,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        let seller = validate_address(deps.api, &order.seller)?;
+        let contract_address = deps.api.addr_validate(&order.contract_address)?;
+        let collection = load_active_collection(deps.storage, &env, &contract_address)?;
+
+        assert_address_not_blocked(deps.storage, &info.sender)?;
+        assert_address_not_blocked(deps.storage, &seller)?;
+        assert_token_not_blocked(deps.storage, &contract_address, &order.token_id)?;
+
+        if order.expires_at <= env.block.time {
+            return Err(ContractError::OrderExpired {});
+        }
+
+        let min_nonce = MIN_NONCES
+            .may_load(deps.storage, seller.clone())?
+            .unwrap_or_default();
+
+        if order.nonce < min_nonce || USED_NONCES.has(deps.storage, (seller.clone(), order.nonce)) {
+            return Err(ContractError::NonceAlreadyUsed { nonce: order.nonce });
+        }
+
+        assert_signer(&seller, &pubkey)?;
+
+        let sign_doc = OrderSignDoc {
+            chain_id: env.block.chain_id.clone(),
+            marketplace: env.contract.address.to_string(),
+            order: order.clone(),
+        };
+        let digest = Sha256::digest(to_vec(&sign_doc)?);
+
+        let is_valid = deps
+            .api
+            .secp256k1_verify(&digest, &signature, &pubkey)
+            .map_err(|_| ContractError::InvalidSignature {})?;
+
+        if !is_valid {
+            return Err(ContractError::InvalidSignature {});
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+
+        if order.price.denom != config.native_denom {
+            return Err(ContractError::DenomNotSupported {});
+        }
+
+        // the seller must still hold the token and approve the marketplace
+        let owner =
+            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
+                .owner_of(&deps.querier, order.token_id.clone(), false)?;
+
+        if owner.owner != seller.to_string() {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        assert_approved(
+            deps.as_ref(),
+            &env,
+            &contract_address,
+            &order.token_id,
+            &seller,
+        )?;
+
+        let fund_input = cw_utils::must_pay(&info, &order.price.denom)?;
+
+        if fund_input < order.price.amount {
+            return Err(ContractError::InsufficientFunds {});
+        }
+
+        if fund_input > order.price.amount {
+            return Err(ContractError::InvalidFunds {
+                expected: order.price.clone(),
+                received: Coin::new(fund_input.u128(), &order.price.denom),
+            });
+        }
+
+        USED_NONCES.save(deps.storage, (seller.clone(), order.nonce), &Empty {})?;
+        let delisted_hooks = remove_stale_sale(deps.storage, &contract_address, &order.token_id)?;
+
+        let (messages, mut sold) = settle(
+            deps.storage,
+            &contract_address,
+            &collection,
+            &order.token_id,
+            &order.price,
+            &seller,
+            &info.sender,
+        )?;
+        sold.source = SaleSource::SignedOrder;
+        sold.nonce = Some(order.nonce);
+
+        Ok(Response::new()
+            .add_submessages(messages)
+            .add_submessages(delisted_hooks)
+            .add_submessages(sale_hooks(deps.storage, &sold)?)
+            .add_event(sold.to_event()))
+    }
+
+    /// Escrows a token received through `SendNft` for a sealed-bid auction.
+    fn start_sealed_auction(
+        deps: DepsMut,
+        env: Env,
+        contract_address: Addr,
+        token_id: String,
+        seller: Addr,
+        terms: SealedAuctionTerms,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+
+        if terms.reserve_price.denom != config.native_denom {
+            return Err(ContractError::DenomNotSupported {});
+        }
+
+        if terms.unrevealed_penalty_percentage > 100 {
+            return Err(ContractError::InvalidPenaltyPercentage {
+                percentage: terms.unrevealed_penalty_percentage,
+            });
+        }
+
+        let commit_starts_at = terms
+            .starts_at
+            .map_or(env.block.time, |starts_at| starts_at.max(env.block.time));
+
+        if terms.commit_ends_at <= commit_starts_at {
+            return Err(ContractError::InvalidAuctionSchedule {
+                reason: "commit phase must end after it starts".to_string(),
+            });
+        }
+
+        if terms.reveal_ends_at <= terms.commit_ends_at {
+            return Err(ContractError::InvalidAuctionSchedule {
+                reason: "reveal phase must end after the commit phase".to_string(),
+            });
+        }
+
+        // a listing made while the seller held the token can no longer be filled
+        let delisted_hooks = remove_stale_sale(deps.storage, &contract_address, &token_id)?;
+
+        SEALED_AUCTIONS.save(
+            deps.storage,
+            (contract_address.clone(), token_id.clone()),
+            &SealedAuction {
+                seller: seller.clone(),
+                terms: terms.clone(),
+                bid_count: 0,
+                highest_bid: None,
+                second_highest_amount: None,
+            },
+        )?;
+
+        Ok(Response::new().add_submessages(delisted_hooks).add_event(
+            Event::new("start_sealed_auction")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("token_id", token_id)
+                .add_attribute("seller", seller)
+                .add_attribute("reserve_price", terms.reserve_price.to_string())
+                .add_attribute("commit_ends_at", terms.commit_ends_at.to_string())
+                .add_attribute("reveal_ends_at", terms.reveal_ends_at.to_string()),
+        ))
+    }
+
+    pub fn cancel_sealed_auction(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let auction = SEALED_AUCTIONS
+            .load(deps.storage, (contract_address.clone(), token_id.clone()))
+            .map_err(|_| ContractError::AuctionDoesNotExist {})?;
+
+        if auction.seller != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if auction.bid_count > 0 {
+            return Err(ContractError::AuctionHasBids {});
+        }
+
+        SEALED_AUCTIONS.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+
+        Ok(Response::new()
+            .add_message(transfer_nft_msg(
+                &contract_address,
+                &token_id,
+                &auction.seller,
+            )?)
+            .add_event(
+                Event::new("cancel_sealed_auction")
+                    .add_attribute("contract_address", contract_address)
+                    .add_attribute("token_id", token_id)
+                    .add_attribute("seller", auction.seller),
+            ))
+    }
+
+    pub fn commit_sealed_bid(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        token_id: String,
+        commitment: Binary,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        load_active_collection(deps.storage, &env, &contract_address)?;
+        assert_address_not_blocked(deps.storage, &info.sender)?;
+        assert_token_not_blocked(deps.storage, &contract_address, &token_id)?;
+
+        let mut auction = SEALED_AUCTIONS
+            .load(deps.storage, (contract_address.clone(), token_id.clone()))
+            .map_err(|_| ContractError::AuctionDoesNotExist {})?;
+
+        let has_started = auction
+            .terms
+            .starts_at
+            .map_or(true, |starts_at| starts_at <= env.block.time);
+
+        if !has_started || env.block.time >= auction.terms.commit_ends_at {
+            return Err(ContractError::CommitPhaseNotOpen {});
+        }
+
+        if auction.seller == info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let bid_key = (
+            contract_address.clone(),
+            token_id.clone(),
+            info.sender.clone(),
+        );
+
+        if SEALED_BIDS.has(deps.storage, bid_key.clone()) {
+            return Err(ContractError::BidAlreadyCommitted {});
+        }
+
+        if auction.bid_count >= MAX_SEALED_BIDS {
+            return Err(ContractError::TooManyBids {
+                max: MAX_SEALED_BIDS,
+            });
+        }
+
+        let reserve_price = &auction.terms.reserve_price;
+        let deposit = cw_utils::must_pay(&info, &reserve_price.denom)?;
+
+        if deposit < reserve_price.amount {
+            return Err(ContractError::InsufficientFunds {});
+        }
+
+        let deposit = Coin::new(deposit.u128(), &reserve_price.denom);
+
+        SEALED_BIDS.save(
+            deps.storage,
+            bid_key,
+            &SealedBid {
+                commitment,
+                deposit: deposit.clone(),
+                revealed_amount: None,
+            },
+        )?;
+
+        auction.bid_count += 1;
+        SEALED_AUCTIONS.save(
+            deps.storage,
+            (contract_address.clone(), token_id.clone()),
+            &auction,
+        )?;
+
+        Ok(Response::new().add_event(
+            Event::new("commit_sealed_bid")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("token_id", token_id)
+                .add_attribute("bidder", info.sender)
+                .add_attribute("deposit", deposit.to_string()),
+        ))
+    }
+
+    /// Checks a bid against its commitment. Reveals are accepted while the marketplace is
+    /// paused so bidders are not penalized for it.
+    pub fn reveal_sealed_bid(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        token_id: String,
+        amount: Uint128,
+        salt: String,
+    ) -> Result<Response, ContractError> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let mut auction = SEALED_AUCTIONS
+            .load(deps.storage, (contract_address.clone(), token_id.clone()))
+            .map_err(|_| ContractError::AuctionDoesNotExist {})?;
+
+        if env.block.time < auction.terms.commit_ends_at
+            || env.block.time >= auction.terms.reveal_ends_at
+        {
+            return Err(ContractError::RevealPhaseNotOpen {});
+        }
+
+        let bid_key = (
+            contract_address.clone(),
+            token_id.clone(),
+            info.sender.clone(),
+        );
+        let mut bid = SEALED_BIDS
+            .load(deps.storage, bid_key.clone())
+            .map_err(|_| ContractError::BidDoesNotExist {})?;
+
+        if bid.revealed_amount.is_some() {
+            return Err(ContractError::BidAlreadyRevealed {});
+        }
+
+        let commitment = Sha256::digest(to_vec(&SealedBidCommitment {
+            contract_address: contract_address.to_string(),
+            token_id: token_id.clone(),
+            bidder: info.sender.to_string(),
+            amount,
+            salt,
+        })?);
+
+        if commitment.as_slice() != bid.commitment.as_slice() {
+            return Err(ContractError::CommitmentMismatch {});
+        }
+
+        bid.revealed_amount = Some(amount);
+        SEALED_BIDS.save(deps.storage, bid_key, &bid)?;
+
+        // a bid below the reserve or above its deposit is revealed but never wins
+        let is_valid = amount >= auction.terms.reserve_price.amount && amount <= bid.deposit.amount;
+
+        if is_valid {
+            match &auction.highest_bid {
+                Some(highest) if amount <= highest.amount => {
+                    auction.second_highest_amount = auction.second_highest_amount.max(Some(amount));
+                }
+                _ => {
+                    auction.second_highest_amount =
+                        auction.highest_bid.as_ref().map(|highest| highest.amount);
+                    auction.highest_bid = Some(RevealedBid {
+                        bidder: info.sender.clone(),
+                        amount,
+                    });
+                }
+            }
+
+            SEALED_AUCTIONS.save(
+                deps.storage,
+                (contract_address.clone(), token_id.clone()),
+                &auction,
+            )?;
+        }
+
+        Ok(Response::new().add_event(
+            Event::new("reveal_sealed_bid")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("token_id", token_id)
+                .add_attribute("bidder", info.sender)
+                .add_attribute("amount", amount)
+                .add_attribute("is_valid", is_valid.to_string()),
+        ))
+    }
+
+    /// Sells the token to the highest valid bid at the auction's pricing, or returns it to the
+    /// seller without one. Every other deposit is refunded, less the penalty on unrevealed bids
+    /// which is paid to the seller. An auction whose token, seller or winner has been blocked
+    /// unwinds instead, refunding every deposit in full and returning the token to the seller.
+    pub fn settle_sealed_auction(
+        deps: DepsMut,
+        env: Env,
+        contract_address: String,
+        token_id: String,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let auction = SEALED_AUCTIONS
+            .load(deps.storage, (contract_address.clone(), token_id.clone()))
+            .map_err(|_| ContractError::AuctionDoesNotExist {})?;
+
+        if env.block.time < auction.terms.reveal_ends_at {
+            return Err(ContractError::AuctionNotEnded {
+                reveal_ends_at: auction.terms.reveal_ends_at,
+            });
+        }
+
+        let collection = load_active_collection(deps.storage, &env, &contract_address)?;
+
+        let is_blocked = BLOCKED_TOKENS
+            .has(deps.storage, (contract_address.clone(), token_id.clone()))
+            || BLOCKED_ADDRESSES.has(deps.storage, auction.seller.clone())
+            || auction.highest_bid.as_ref().map_or(false, |highest| {
+                BLOCKED_ADDRESSES.has(deps.storage, highest.bidder.clone())
+            });
+
+        if is_blocked {
+            let messages =
+                unwind_sealed_auction(deps.storage, &contract_address, &token_id, &auction)?;
+
+            return Ok(Response::new().add_submessages(messages).add_event(
+                Event::new("unwind_sealed_auction")
+                    .add_attribute("contract_address", contract_address)
+                    .add_attribute("token_id", token_id)
+                    .add_attribute("seller", auction.seller),
+            ));
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+        let denom = auction.terms.reserve_price.denom.clone();
+
+        let winner = auction.highest_bid.as_ref().map(|highest| {
+            let price = match auction.terms.pricing {
+                AuctionPricing::FirstPrice => highest.amount,
+                AuctionPricing::SecondPrice => auction
+                    .second_highest_amount
+                    .unwrap_or(auction.terms.reserve_price.amount),
+            };
+
+            (highest.bidder.clone(), price)
+        });
+
+        let bids = SEALED_BIDS
+            .prefix((contract_address.clone(), token_id.clone()))
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut messages: Vec<SubMsg> = Vec::new();
+        let mut penalty = Uint128::zero();
+
+        for (bidder, bid) in bids {
+            SEALED_BIDS.remove(
+                deps.storage,
+                (contract_address.clone(), token_id.clone(), bidder.clone()),
+            );
+
+            let mut refund = bid.deposit.amount;
+
+            match &winner {
+                Some((winner, price)) if *winner == bidder => refund -= *price,
+                _ if bid.revealed_amount.is_none() => {
+                    let forfeited =
+                        refund * Decimal::percent(auction.terms.unrevealed_penalty_percentage);
+                    penalty += forfeited;
+                    refund -= forfeited;
+                }
+                _ => {}
+            }
+
+            if !refund.is_zero() {
+                messages.push(SubMsg::new(BankMsg::Send {
+                    to_address: bidder.to_string(),
+                    amount: coins(refund.u128(), &denom),
+                }));
+            }
+        }
+
+        if !penalty.is_zero() {
+            messages.extend(
+                pay_out(
+                    deps.storage,
+                    &config,
+                    &auction.seller,
+                    Coin::new(penalty.u128(), &denom),
+                )?
+                .map(SubMsg::new),
+            );
+        }
+
+        SEALED_AUCTIONS.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+
+        let event = Event::new("settle_sealed_auction")
+            .add_attribute("contract_address", contract_address.clone())
+            .add_attribute("token_id", token_id.clone())
+            .add_attribute("seller", auction.seller.clone())
+            .add_attribute("unrevealed_penalty", penalty);
+
+        match winner {
+            Some((winner, price)) => {
+                let (sale_messages, mut sold) = settle(
+                    deps.storage,
+                    &contract_address,
+                    &collection,
+                    &token_id,
+                    &Coin::new(price.u128(), &denom),
+                    &auction.seller,
+                    &winner,
+                )?;
+                sold.source = SaleSource::SealedAuction;
+                messages.extend(sale_messages);
+
+                Ok(Response::new()
+                    .add_submessages(messages)
+                    .add_submessages(sale_hooks(deps.storage, &sold)?)
+                    .add_event(event.add_attribute("winner", winner))
+                    .add_event(sold.to_event()))
+            }
+            None => {
+                messages.push(SubMsg::new(transfer_nft_msg(
+                    &contract_address,
+                    &token_id,
+                    &auction.seller,
+                )?));
+
+                Ok(Response::new()
+                    .add_submessages(messages)
+                    .add_event(event.add_attribute("winner", "null")))
+            }
+        }
+    }
+
+    /// Refunds every deposit of a sealed auction in full and returns the token to the seller.
+    fn unwind_sealed_auction(
+        storage: &mut dyn Storage,
+        contract_address: &Addr,
+        token_id: &str,
+        auction: &SealedAuction,
+    ) -> StdResult<Vec<SubMsg>> {
+        let bids = SEALED_BIDS
+            .prefix((contract_address.clone(), token_id.to_string()))
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut messages: Vec<SubMsg> = Vec::new();
+
+        for (bidder, bid) in bids {
+            SEALED_BIDS.remove(
+                storage,
+                (
+                    contract_address.clone(),
+                    token_id.to_string(),
+                    bidder.clone(),
+                ),
+            );
+            messages.push(SubMsg::new(BankMsg::Send {
+                to_address: bidder.to_string(),
+                amount: vec![bid.deposit],
+            }));
+        }
+
+        SEALED_AUCTIONS.remove(storage, (contract_address.clone(), token_id.to_string()));
+        messages.push(SubMsg::new(transfer_nft_msg(
+            contract_address,
+            token_id,
+            &auction.seller,
+        )?));
+
+        Ok(messages)
+    }
+
+    pub fn create_group_buy(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        token_id: String,
+        recipient: String,
+        deadline: Timestamp,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let recipient = validate_address(deps.api, &recipient)?;
+        assert_address_not_blocked(deps.storage, &info.sender)?;
+        assert_address_not_blocked(deps.storage, &recipient)?;
+
+        let sale = SALES
+            .load(deps.storage, (contract_address.clone(), token_id.clone()))
+            .map_err(|_| ContractError::SaleDoesNotExist {})?;
+
+        if deadline <= env.block.time {
+            return Err(ContractError::InvalidGroupBuyDeadline { deadline });
+        }
+
+        // the pool buys without a Merkle proof, so it cannot fill a private sale
+        if sale.merkle_allowlist.is_some() {
+            return Err(ContractError::GroupBuyOnPrivateSale {});
+        }
+
+        let group_buy_id = NEXT_GROUP_BUY_ID
+            .may_load(deps.storage)?
+            .unwrap_or_default();
+        NEXT_GROUP_BUY_ID.save(deps.storage, &(group_buy_id + 1))?;
+        GROUP_BUYS.save(
+            deps.storage,
+            group_buy_id,
+            &GroupBuy {
+                contract_address: contract_address.clone(),
+                token_id: token_id.clone(),
+                recipient: recipient.clone(),
+                price: sale.price.clone(),
+                listing_version: sale.version,
+                deadline,
+                total: Uint128::zero(),
+                is_settled: false,
+            },
+        )?;
+
+        Ok(Response::new().add_event(
+            Event::new("create_group_buy")
+                .add_attribute("group_buy_id", group_buy_id.to_string())
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("token_id", token_id)
+                .add_attribute("recipient", recipient)
+                .add_attribute("price", sale.price.to_string())
+                .add_attribute("deadline", deadline.to_string()),
+        ))
+    }
+
+    /// Adds the sent funds to a group buy, refunding whatever exceeds the amount still needed.
+    pub fn contribute_to_group_buy(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        group_buy_id: u64,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+        assert_address_not_blocked(deps.storage, &info.sender)?;
+
+        let mut group_buy = GROUP_BUYS
+            .load(deps.storage, group_buy_id)
+            .map_err(|_| ContractError::GroupBuyDoesNotExist {})?;
+
+        if group_buy.is_settled
+            || env.block.time >= group_buy.deadline
+            || !is_group_buy_listing_current(deps.storage, &group_buy)?
+        {
+            return Err(ContractError::GroupBuyClosed {});
+        }
+
+        let fund_input = cw_utils::must_pay(&info, &group_buy.price.denom)?;
+        let accepted = fund_input.min(group_buy.price.amount - group_buy.total);
+
+        if accepted.is_zero() {
+            return Err(ContractError::GroupBuyClosed {});
+        }
+
+        GROUP_BUY_CONTRIBUTIONS.update(
+            deps.storage,
+            (group_buy_id, info.sender.clone()),
+            |contribution| -> StdResult<_> {
+                Ok(contribution.unwrap_or_default().checked_add(accepted)?)
+            },
+        )?;
+        group_buy.total += accepted;
+        GROUP_BUYS.save(deps.storage, group_buy_id, &group_buy)?;
+
+        let mut messages: Vec<CosmosMsg> = Vec::new();
+        let excess = fund_input - accepted;
+
+        if !excess.is_zero() {
+            messages.push(
+                BankMsg::Send {
+                    to_address: info.sender.to_string(),
+                    amount: coins(excess.u128(), &group_buy.price.denom),
+                }
+                .into(),
+            );
+        }
+
+        Ok(Response::new().add_messages(messages).add_event(
+            Event::new("contribute_to_group_buy")
+                .add_attribute("group_buy_id", group_buy_id.to_string())
+                .add_attribute("contributor", info.sender)
+                .add_attribute("amount", accepted)
+                .add_attribute("total", group_buy.total),
+        ))
+    }
+
+    /// Buys the listing for the group buy's recipient once it is fully funded. Callable by
+    /// anyone.
+    pub fn settle_group_buy(
+        deps: DepsMut,
+        env: Env,
+        group_buy_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut group_buy = GROUP_BUYS
+            .load(deps.storage, group_buy_id)
+            .map_err(|_| ContractError::GroupBuyDoesNotExist {})?;
+
+        if group_buy.is_settled {
+            return Err(ContractError::GroupBuyClosed {});
+        }
+
+        if env.block.time >= group_buy.deadline {
+            return Err(ContractError::GroupBuyExpired {
+                deadline: group_buy.deadline,
+            });
+        }
+
+        if group_buy.total < group_buy.price.amount {
+            return Err(ContractError::GroupBuyNotFunded {
+                remaining: group_buy.price.amount - group_buy.total,
+            });
+        }
+
+        group_buy.is_settled = true;
+        GROUP_BUYS.save(deps.storage, group_buy_id, &group_buy)?;
+
+        // the pool buys as its recipient, pinned to the listing it was funded for
+        let info = MessageInfo {
+            sender: group_buy.recipient.clone(),
+            funds: vec![group_buy.price.clone()],
+        };
+
+        Ok(buy(
+            deps,
+            env,
+            info,
+            group_buy.contract_address.to_string(),
+            group_buy.token_id,
+            Some(group_buy.price),
+            Some(group_buy.listing_version),
+            None,
+        )?
+        .add_event(
+            Event::new("settle_group_buy")
+                .add_attribute("group_buy_id", group_buy_id.to_string())
+                .add_attribute("recipient", group_buy.recipient),
+        ))
+    }
+
+    /// Returns a contribution once the group buy can no longer complete: its listing was
+    /// removed or changed, or its deadline passed.
+    pub fn withdraw_group_buy_contribution(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        group_buy_id: u64,
+    ) -> Result<Response, ContractError> {
+        let mut group_buy = GROUP_BUYS
+            .load(deps.storage, group_buy_id)
+            .map_err(|_| ContractError::GroupBuyDoesNotExist {})?;
+
+        if group_buy.is_settled {
+            return Err(ContractError::GroupBuyClosed {});
+        }
+
+        if env.block.time < group_buy.deadline
+            && is_group_buy_listing_current(deps.storage, &group_buy)?
+        {
+            return Err(ContractError::GroupBuyStillOpen {});
+        }
+
+        let contribution = GROUP_BUY_CONTRIBUTIONS
+            .may_load(deps.storage, (group_buy_id, info.sender.clone()))?
+            .ok_or(ContractError::NothingToWithdraw {})?;
+
+        GROUP_BUY_CONTRIBUTIONS.remove(deps.storage, (group_buy_id, info.sender.clone()));
+        group_buy.total -= contribution;
+        GROUP_BUYS.save(deps.storage, group_buy_id, &group_buy)?;
+
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: coins(contribution.u128(), &group_buy.price.denom),
+            })
+            .add_event(
+                Event::new("withdraw_group_buy_contribution")
+                    .add_attribute("group_buy_id", group_buy_id.to_string())
+                    .add_attribute("contributor", info.sender)
+                    .add_attribute("amount", contribution),
+            ))
+    }
+
+    /// Whether the listing a group buy was created for is still up at the same version, and
+    /// so at the same price.
+    fn is_group_buy_listing_current(
+        storage: &dyn Storage,
+        group_buy: &GroupBuy,
+    ) -> StdResult<bool> {
+        let sale = SALES.may_load(
+            storage,
+            (
+                group_buy.contract_address.clone(),
+                group_buy.token_id.clone(),
+            ),
+        )?;
+
+        Ok(sale.map_or(false, |sale| sale.version == group_buy.listing_version))
+    }
+
+    /// Opens a primary sale for a collection whose minter is handing the minter role to the
+    /// marketplace. The minter must first propose the transfer with the collection's
+    /// `UpdateOwnership`, which is accepted here.
+    pub fn create_launchpad(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        terms: LaunchpadTerms,
+    ) -> Result<Response, ContractError> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        load_active_collection(deps.storage, &env, &contract_address)?;
+
+        if LAUNCHPADS.has(deps.storage, contract_address.clone()) {
+            return Err(ContractError::LaunchpadAlreadyExists {});
+        }
+
+        let minter =
+            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
+                .minter(&deps.querier)?;
+
+        if minter.minter.as_deref() != Some(info.sender.as_str()) {
+            return Err(ContractError::NotCollectionMinter {});
+        }
+
+        if terms.max_supply == 0 {
+            return Err(ContractError::InvalidMaxSupply {});
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+        validate_mint_phases(&config, &terms.phases)?;
+
+        let payment_address = match terms.payment_address {
+            Some(payment_address) => validate_address(deps.api, &payment_address)?,
+            None => info.sender.clone(),
+        };
+
+        LAUNCHPADS.save(
+            deps.storage,
+            contract_address.clone(),
+            &Launchpad {
+                creator: info.sender.clone(),
+                payment_address,
+                phase_minted: vec![0; terms.phases.len()],
+                phases: terms.phases,
+                max_supply: terms.max_supply,
+                minted: 0,
+                starting_token_id: terms.starting_token_id,
+                base_token_uri: terms.base_token_uri,
+            },
+        )?;
+
+        let accept_minter =
+            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
+                .call(ExecuteMsg::<Empty>::UpdateOwnership(
+                    cw_ownable::Action::AcceptOwnership,
+                ))?;
+
+        Ok(Response::new().add_message(accept_minter).add_event(
+            Event::new("create_launchpad")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("creator", info.sender)
+                .add_attribute("max_supply", terms.max_supply.to_string()),
+        ))
+    }
+
+    /// Phases must be in the native denom and follow each other without overlapping, so at
+    /// most one is active at a time.
+    fn validate_mint_phases(config: &Config, phases: &[MintPhase]) -> Result<(), ContractError> {
+        if phases.is_empty() {
+            return Err(ContractError::InvalidMintPhases {
+                reason: "at least one phase is required".to_string(),
+            });
+        }
+
+        for phase in phases {
+            if phase.price.denom != config.native_denom {
+                return Err(ContractError::DenomNotSupported {});
+            }
+
+            if let Some(allowlist) = &phase.merkle_allowlist {
+                validate_merkle_allowlist(allowlist)?;
+            }
+
+            if phase.ends_at <= phase.starts_at {
+                return Err(ContractError::InvalidMintPhases {
+                    reason: format!("phase {} must end after it starts", phase.name),
+                });
+            }
+
+            if phase.max_supply == Some(0) {
+                return Err(ContractError::InvalidMintPhases {
+                    reason: format!("phase {} must allow at least one mint", phase.name),
+                });
+            }
+        }
+
+        for pair in phases.windows(2) {
+            if pair[1].starts_at < pair[0].ends_at {
+                return Err(ContractError::InvalidMintPhases {
+                    reason: format!("phase {} starts before {} ends", pair[1].name, pair[0].name),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn update_launchpad_allowlist(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+        phase_id: u32,
+        add: Vec<String>,
+        remove: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let launchpad = LAUNCHPADS
+            .load(deps.storage, contract_address.clone())
+            .map_err(|_| ContractError::LaunchpadDoesNotExist {})?;
+
+        if launchpad.creator != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if launchpad.phases.len() <= phase_id as usize {
+            return Err(ContractError::MintPhaseDoesNotExist { phase_id });
+        }
+
+        for address in &add {
+            let address = validate_address(deps.api, address)?;
+            LAUNCHPAD_ALLOWLISTS.save(
+                deps.storage,
+                (contract_address.clone(), phase_id, address),
+                &Empty {},
+            )?;
+        }
+
+        for address in &remove {
+            let address = validate_address(deps.api, address)?;
+            LAUNCHPAD_ALLOWLISTS
+                .remove(deps.storage, (contract_address.clone(), phase_id, address));
+        }
+
+        Ok(Response::new().add_event(
+            Event::new("update_launchpad_allowlist")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("phase_id", phase_id.to_string())
+                .add_attribute("added", add.len().to_string())
+                .add_attribute("removed", remove.len().to_string()),
+        ))
+    }
+
+    /// Mints the next launchpad token to the sender in the active phase, paying the taker fee
+    /// and the rest of the price to the launchpad's payment address.
+    pub fn mint(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+        merkle_proof: Option<MerkleProof>,
+    ) -> Result<Response, ContractError> {
+        assert_not_paused(deps.storage, &env)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        load_active_collection(deps.storage, &env, &contract_address)?;
+        assert_address_not_blocked(deps.storage, &info.sender)?;
+
+        let mut launchpad = LAUNCHPADS
+            .load(deps.storage, contract_address.clone())
+            .map_err(|_| ContractError::LaunchpadDoesNotExist {})?;
+
+        if launchpad.minted >= launchpad.max_supply {
+            return Err(ContractError::LaunchpadSoldOut {});
+        }
+
+        let (phase_id, phase) = launchpad
+            .phases
+            .iter()
+            .enumerate()
+            .find(|(_, phase)| phase.starts_at <= env.block.time && env.block.time < phase.ends_at)
+            .map(|(phase_id, phase)| (phase_id as u32, phase.clone()))
+            .ok_or(ContractError::NoActiveMintPhase {})?;
+
+        // launchpads stored before phase supplies were tracked start counting from zero
+        launchpad.phase_minted.resize(launchpad.phases.len(), 0);
+
+        if let Some(max_supply) = phase.max_supply {
+            if launchpad.phase_minted[phase_id as usize] >= max_supply {
+                return Err(ContractError::MintPhaseSoldOut { phase_id });
+            }
+        }
+
+        match &phase.merkle_allowlist {
+            Some(allowlist) => consume_merkle_allocation(
+                deps.storage,
+                allowlist,
+                &info.sender,
+                merkle_proof.as_ref(),
+            )?,
+            None if phase.is_allowlisted
+                && !LAUNCHPAD_ALLOWLISTS.has(
+                    deps.storage,
+                    (contract_address.clone(), phase_id, info.sender.clone()),
+                ) =>
+            {
+                return Err(ContractError::NotAllowlisted {});
+            }
+            None => {}
+        }
+
+        let mints_key = (contract_address.clone(), phase_id, info.sender.clone());
+        let minted_by_sender = LAUNCHPAD_MINTS
+            .may_load(deps.storage, mints_key.clone())?
+            .unwrap_or_default();
+
+        if let Some(limit) = phase.per_wallet_limit {
+            if minted_by_sender >= limit {
+                return Err(ContractError::MintLimitReached { limit });
+            }
+        }
+
+        let mut messages: Vec<CosmosMsg> = Vec::new();
+
+        if phase.price.amount.is_zero() {
+            cw_utils::nonpayable(&info)?;
+        } else {
+            let fund_input = cw_utils::must_pay(&info, &phase.price.denom)?;
+
+            if fund_input != phase.price.amount {
+                return Err(ContractError::InvalidFunds {
+                    expected: phase.price,
+                    received: Coin::new(fund_input.u128(), &phase.price.denom),
+                });
+            }
+
+            let config = CONFIG.load(deps.storage)?;
+            let taker_fee = phase.price.amount * Decimal::percent(config.taker_fee);
+            let creator_proceeds = phase.price.amount - taker_fee;
+
+            if !taker_fee.is_zero() {
+                messages.extend(pay_out(
+                    deps.storage,
+                    &config,
+                    &config.taker_address,
+                    Coin::new(taker_fee.u128(), &phase.price.denom),
+                )?);
+            }
+
+            if !creator_proceeds.is_zero() {
+                messages.extend(pay_out(
+                    deps.storage,
+                    &config,
+                    &launchpad.payment_address,
+                    Coin::new(creator_proceeds.u128(), &phase.price.denom),
+                )?);
+            }
+        }
+
+        let token_id = (launchpad.starting_token_id + launchpad.minted).to_string();
+        let token_uri = launchpad
+            .base_token_uri
+            .as_ref()
+            .map(|base_token_uri| format!("{}/{}", base_token_uri, token_id));
+
+        launchpad.minted += 1;
+        launchpad.phase_minted[phase_id as usize] += 1;
+        LAUNCHPADS.save(deps.storage, contract_address.clone(), &launchpad)?;
+        LAUNCHPAD_MINTS.save(deps.storage, mints_key, &(minted_by_sender + 1))?;
+
+        messages.push(
+            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
+                .call(ExecuteMsg::<Empty>::Mint {
+                    token_id: token_id.clone(),
+                    owner: info.sender.to_string(),
+                    token_uri,
+                    extension: Empty {},
+                })?,
+        );
+
+        Ok(Response::new().add_messages(messages).add_event(
+            Event::new("launchpad_mint")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("token_id", token_id)
+                .add_attribute("minter", info.sender)
+                .add_attribute("phase", phase.name)
+                .add_attribute("price", phase.price.to_string()),
+        ))
+    }
+
+    /// Closes a launchpad and proposes returning the minter role to its creator, who accepts
+    /// it with the collection's `UpdateOwnership`.
+    pub fn close_launchpad(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+    ) -> Result<Response, ContractError> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let launchpad = LAUNCHPADS
+            .load(deps.storage, contract_address.clone())
+            .map_err(|_| ContractError::LaunchpadDoesNotExist {})?;
+
+        if launchpad.creator != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        LAUNCHPADS.remove(deps.storage, contract_address.clone());
+
+        let return_minter =
+            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
+                .call(ExecuteMsg::<Empty>::UpdateOwnership(
+                    cw_ownable::Action::TransferOwnership {
+                        new_owner: launchpad.creator.to_string(),
+                        expiry: None,
+                    },
+                ))?;
+
+        Ok(Response::new().add_message(return_minter).add_event(
+            Event::new("close_launchpad")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("minted", launchpad.minted.to_string()),
+        ))
+    }
+
+    /// Sets or clears the Merkle allowlist of a launchpad phase, by its creator or the contract
+    /// owner, or of a listing, by its seller only.
+    pub fn update_merkle_allowlist(
+        deps: DepsMut,
+        info: MessageInfo,
+        contract_address: String,
+        target: MerkleAllowlistTarget,
+        allowlist: Option<MerkleAllowlist>,
+    ) -> Result<Response, ContractError> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+
+        if let Some(allowlist) = &allowlist {
+            validate_merkle_allowlist(allowlist)?;
+        }
+
+        let event = match target {
+            MerkleAllowlistTarget::MintPhase { phase_id } => {
+                let mut launchpad = LAUNCHPADS
+                    .load(deps.storage, contract_address.clone())
+                    .map_err(|_| ContractError::LaunchpadDoesNotExist {})?;
+
+                if launchpad.creator != info.sender
+                    && cw_ownable::assert_owner(deps.storage, &info.sender).is_err()
+                {
+                    return Err(ContractError::Unauthorized {});
+                }
+
+                let phase = launchpad
+                    .phases
+                    .get_mut(phase_id as usize)
+                    .ok_or(ContractError::MintPhaseDoesNotExist { phase_id })?;
+                phase.merkle_allowlist = allowlist.clone();
+                LAUNCHPADS.save(deps.storage, contract_address.clone(), &launchpad)?;
+
+                Event::new("update_merkle_allowlist")
+                    .add_attribute("phase_id", phase_id.to_string())
+            }
+            MerkleAllowlistTarget::Sale { token_id } => {
+                let mut sale = SALES
+                    .load(deps.storage, (contract_address.clone(), token_id.clone()))
+                    .map_err(|_| ContractError::SaleDoesNotExist {})?;
+
+                if sale.owner_address != info.sender {
+                    return Err(ContractError::Unauthorized {});
+                }
+
+                // restricting a listing changes its terms, releasing buyers pinned to them
+                let version = LISTING_VERSION.may_load(deps.storage)?.unwrap_or_default() + 1;
+                LISTING_VERSION.save(deps.storage, &version)?;
+                sale.version = version;
+                sale.merkle_allowlist = allowlist.clone();
+                SALES.save(
+                    deps.storage,
+                    (contract_address.clone(), token_id.clone()),
+                    &sale,
+                )?;
+
+                Event::new("update_merkle_allowlist").add_attribute("token_id", token_id)
+            }
+        };
+
+        Ok(Response::new().add_event(
+            event
+                .add_attribute("contract_address", contract_address)
+                .add_attribute(
+                    "merkle_root",
+                    allowlist.map_or("null".to_string(), |allowlist| allowlist.root.to_base64()),
+                ),
+        ))
+    }
+
+    fn validate_merkle_allowlist(allowlist: &MerkleAllowlist) -> Result<(), ContractError> {
+        if allowlist.root.len() != 32 {
+            return Err(ContractError::InvalidMerkleRoot {});
+        }
+
+        Ok(())
+    }
+
+    /// Checks `address` against a Merkle allowlist and consumes one of its allocations.
+    /// Allocations are counted per root, so allowlists sharing a root share allocations.
+    fn consume_merkle_allocation(
+        storage: &mut dyn Storage,
+        allowlist: &MerkleAllowlist,
+        address: &Addr,
+        merkle_proof: Option<&MerkleProof>,
+    ) -> Result<(), ContractError> {
+        let merkle_proof = merkle_proof.ok_or(ContractError::NotAllowlisted {})?;
+
+        let leaf = match (allowlist.with_allocations, merkle_proof.allocation) {
+            (false, _) => Sha256::digest(address.as_bytes()),
+            (true, Some(allocation)) => Sha256::digest(format!("{}:{}", address, allocation)),
+            (true, None) => return Err(ContractError::InvalidMerkleProof {}),
+        };
+
+        // pairs are hashed in sorted order, so the proof needs no left/right flags
+        let mut hash = leaf.to_vec();
+        for sibling in &merkle_proof.proof {
+            let mut hasher = Sha256::new();
+            if hash.as_slice() <= sibling.as_slice() {
+                hasher.update(&hash);
+                hasher.update(sibling.as_slice());
+            } else {
+                hasher.update(sibling.as_slice());
+                hasher.update(&hash);
+            }
+            hash = hasher.finalize().to_vec();
+        }
+
+        if hash.as_slice() != allowlist.root.as_slice() {
+            return Err(ContractError::InvalidMerkleProof {});
+        }
+
+        let key = (allowlist.root.as_slice(), address.clone());
+        let claimed = MERKLE_CLAIMS
+            .may_load(storage, key.clone())?
+            .unwrap_or_default();
+
+        if let (true, Some(allocation)) = (allowlist.with_allocations, merkle_proof.allocation) {
+            if claimed >= allocation {
+                return Err(ContractError::AllocationExhausted { allocation });
+            }
+        }
+
+        MERKLE_CLAIMS.save(storage, key, &(claimed + 1))?;
+
+        Ok(())
+    }
+
+    /// Stops all trading in a collection. Its listings, offers and auctions are then removed
+    /// in batches by `CleanupDeregisteredCollection`, after which the collection is removed.
+    pub fn deregister_collection(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        contract_address: String,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::CollectionCurator)?;
+
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let mut collection = COLLECTIONS
+            .load(deps.storage, contract_address.clone())
+            .map_err(|_| ContractError::CollectionNotExist {})?;
+
+        if collection.is_deregistered {
+            return Err(ContractError::CollectionDeregistered {
+                contract_address: contract_address.to_string(),
+            });
+        }
+
+        collection.is_deregistered = true;
+        COLLECTIONS.save(deps.storage, contract_address.clone(), &collection)?;
+        DEREGISTRATIONS.save(
+            deps.storage,
+            contract_address.clone(),
+            &Deregistration {
+                deregistered_by: info.sender.clone(),
+                deregistered_at: env.block.time,
+                sales_removed: 0,
+                offers_refunded: 0,
+                auctions_cancelled: 0,
+                completed_at: None,
+            },
+        )?;
+
+        Ok(Response::new().add_event(
+            Event::new("deregister_collection")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("deregistered_by", info.sender),
+        ))
+    }
+
+    /// Removes up to `limit` listings, offers and auction bids of a deregistered collection,
+    /// returning escrowed tokens and refunding offers and bids. Callable by anyone until the
+    /// cleanup completes and the collection is removed.
+    pub fn cleanup_deregistered_collection(
+        deps: DepsMut,
+        env: Env,
+        contract_address: String,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let mut deregistration = DEREGISTRATIONS
+            .may_load(deps.storage, contract_address.clone())?
+            .filter(|deregistration| deregistration.completed_at.is_none())
+            .ok_or(ContractError::CollectionNotDeregistered {
+                contract_address: contract_address.to_string(),
+            })?;
+
+        let mut budget = limit
+            .unwrap_or(DEFAULT_CLEANUP_LIMIT)
+            .min(MAX_CLEANUP_LIMIT) as usize;
+        let mut messages: Vec<SubMsg> = Vec::new();
+
+        let sales = SALES
+            .prefix(contract_address.clone())
+            .range(deps.storage, None, None, Order::Ascending)
+            .take(budget)
+            .collect::<StdResult<Vec<_>>>()?;
+        budget -= sales.len();
+
+        for (token_id, sale) in sales {
+            SALES.remove(deps.storage, (contract_address.clone(), token_id.clone()));
+
+            if sale.is_escrowed {
+                messages.push(SubMsg::new(transfer_nft_msg(
+                    &contract_address,
+                    &token_id,
+                    &sale.owner_address,
+                )?));
+            }
+
+            messages.extend(prepare_hooks(
+                deps.storage,
+                MarketplaceHookMsg::Delisted {
+                    contract_address: contract_address.to_string(),
+                    token_id,
+                    seller: sale.owner_address.to_string(),
+                },
+            )?);
+            deregistration.sales_removed += 1;
+        }
+
+        let offers = COLLECTION_OFFERS
+            .prefix(contract_address.clone())
+            .range(deps.storage, None, None, Order::Ascending)
+            .take(budget)
+            .collect::<StdResult<Vec<_>>>()?;
+        budget -= offers.len();
+
+        for (offeror, offer) in offers {
+            COLLECTION_OFFERS.remove(deps.storage, (contract_address.clone(), offeror.clone()));
+            messages.push(SubMsg::new(BankMsg::Send {
+                to_address: offeror.to_string(),
+                amount: vec![offer.price],
+            }));
+            deregistration.offers_refunded += 1;
+        }
+
+        let auctions = SEALED_AUCTIONS
+            .prefix(contract_address.clone())
+            .range(deps.storage, None, None, Order::Ascending)
+            .take(budget)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        // an auction is cancelled whole, so its bids may run past the budget
+        for (token_id, auction) in auctions {
+            if budget == 0 {
+                break;
+            }
+            budget = budget.saturating_sub(1 + auction.bid_count as usize);
+
+            messages.extend(unwind_sealed_auction(
+                deps.storage,
+                &contract_address,
+                &token_id,
+                &auction,
+            )?);
+            deregistration.auctions_cancelled += 1;
+        }
+
+        let is_complete = SALES
+            .prefix(contract_address.clone())
+            .keys_raw(deps.storage, None, None, Order::Ascending)
+            .next()
+            .is_none()
+            && COLLECTION_OFFERS
+                .prefix(contract_address.clone())
+                .keys_raw(deps.storage, None, None, Order::Ascending)
+                .next()
+                .is_none()
+            && SEALED_AUCTIONS
+                .prefix(contract_address.clone())
+                .keys_raw(deps.storage, None, None, Order::Ascending)
+                .next()
+                .is_none();
+
+        if is_complete {
+            COLLECTIONS.remove(deps.storage, contract_address.clone());
+            COLLECTION_METADATA.remove(deps.storage, contract_address.clone());
+            deregistration.completed_at = Some(env.block.time);
+        }
+
+        DEREGISTRATIONS.save(deps.storage, contract_address.clone(), &deregistration)?;
+
+        Ok(Response::new().add_submessages(messages).add_event(
+            Event::new("cleanup_deregistered_collection")
+                .add_attribute("contract_address", contract_address)
+                .add_attribute("sales_removed", deregistration.sales_removed.to_string())
+                .add_attribute(
+                    "offers_refunded",
+                    deregistration.offers_refunded.to_string(),
+                )
+                .add_attribute(
+                    "auctions_cancelled",
+                    deregistration.auctions_cancelled.to_string(),
+                )
+                .add_attribute("is_complete", is_complete.to_string()),
+        ))
+    }
+
+    pub fn cancel_order_nonces(
+        deps: DepsMut,
+        info: MessageInfo,
+        nonces: Vec<u64>,
+    ) -> Result<Response, ContractError> {
+        for nonce in &nonces {
+            USED_NONCES.save(deps.storage, (info.sender.clone(), *nonce), &Empty {})?;
+        }
+
+        Ok(Response::new().add_event(
+            Event::new("cancel_order_nonces")
+                .add_attribute("seller", info.sender)
+                .add_attribute(
+                    "nonces",
+                    nonces
+                        .iter()
+                        .map(|nonce| nonce.to_string())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ),
+        ))
+    }
+
+    pub fn increase_min_nonce(
+        deps: DepsMut,
+        info: MessageInfo,
+        min_nonce: u64,
+    ) -> Result<Response, ContractError> {
+        let current = MIN_NONCES
+            .may_load(deps.storage, info.sender.clone())?
+            .unwrap_or_default();
+
+        if min_nonce <= current {
+            return Err(ContractError::InvalidMinNonce { min_nonce, current });
+        }
+
+        MIN_NONCES.save(deps.storage, info.sender.clone(), &min_nonce)?;
+
+        Ok(Response::new().add_event(
+            Event::new("increase_min_nonce")
+                .add_attribute("seller", info.sender)
+                .add_attribute("min_nonce", min_nonce.to_string()),
+        ))
+    }
+
+    /// Checks that `pubkey` hashes to the account bytes of the bech32 `address`.
+    fn assert_signer(address: &Addr, pubkey: &[u8]) -> Result<(), ContractError> {
+        let account = bech32::decode(address.as_str())
+            .ok()
+            .and_then(|(_, data, _)| Vec::<u8>::from_base32(&data).ok())
+            .ok_or(ContractError::InvalidSignature {})?;
+
+        if account != Ripemd160::digest(Sha256::digest(pubkey)).as_slice() {
+            return Err(ContractError::InvalidSignature {});
+        }
+
+        Ok(())
+    }
+
+    pub fn withdraw(
+        deps: DepsMut,
+        info: MessageInfo,
+        denom: String,
+    ) -> Result<Response, ContractError> {
+        let amount = BALANCES
+            .may_load(deps.storage, (info.sender.clone(), &denom))?
+            .unwrap_or_default();
+
+        if amount.is_zero() {
+            return Err(ContractError::NothingToWithdraw {});
+        }
+
+        BALANCES.remove(deps.storage, (info.sender.clone(), &denom));
+
+        Ok(Response::new()
+            .add_message(BankMsg::Send {
+                to_address: info.sender.to_string(),
+                amount: coins(amount.u128(), &denom),
+            })
+            .add_event(
+                Event::new("withdraw")
+                    .add_attribute("address", info.sender)
+                    .add_attribute("denom", denom)
+                    .add_attribute("amount", amount),
+            ))
+    }
+
+    pub fn pause_marketplace(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        expires_at_height: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::Pauser)?;
+
+        if let Some(height) = expires_at_height {
+            if height <= env.block.height {
+                return Err(ContractError::InvalidPauseExpiry { height });
+            }
+        }
+
+        PAUSE.save(
+            deps.storage,
+            &Pause {
+                paused_by: info.sender.clone(),
+                expires_at_height,
+            },
+        )?;
+
+        Ok(Response::new().add_event(
+            MarketplacePaused {
+                paused_by: info.sender,
+                expires_at_height,
+            }
+            .to_event(),
+        ))
+    }
+
+    pub fn unpause_marketplace(
+        deps: DepsMut,
+        info: MessageInfo,
+    ) -> Result<Response, ContractError> {
+        assert_owner_or_role(deps.storage, &info.sender, Role::Pauser)?;
+
+        PAUSE.remove(deps.storage);
+
+        Ok(Response::new().add_event(
+            MarketplaceUnpaused {
+                unpaused_by: info.sender,
+            }
+            .to_event(),
+        ))
+    }
+
+    fn assert_address_not_blocked(
+        storage: &dyn Storage,
+        address: &Addr,
+    ) -> Result<(), ContractError> {
+        if let Some(entry) = BLOCKED_ADDRESSES.may_load(storage, address.clone())? {
+            return Err(ContractError::AddressBlocked {
+                address: address.to_string(),
+                reason: entry.reason,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn assert_token_not_blocked(
+        storage: &dyn Storage,
+        contract_address: &Addr,
+        token_id: &str,
+    ) -> Result<(), ContractError> {
+        if let Some(entry) =
+            BLOCKED_TOKENS.may_load(storage, (contract_address.clone(), token_id.to_string()))?
+        {
+            return Err(ContractError::TokenBlocked {
+                contract_address: contract_address.to_string(),
+                token_id: token_id.to_string(),
+                reason: entry.reason,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Whether `address` is the cw721 minter or the wasm admin of `contract_address`, or
+    /// created its launchpad. The launchpad holds the minter role on its creator's behalf
+    /// until `CloseLaunchpad` hands it back, so the creator keeps managing the collection
+    /// for as long as it is open, including after its phases end.
+    pub fn is_collection_creator(
+        deps: Deps,
+        contract_address: &Addr,
+        address: &Addr,
+    ) -> StdResult<bool> {
+        if let Some(launchpad) = LAUNCHPADS.may_load(deps.storage, contract_address.clone())? {
+            if launchpad.creator == *address {
+                return Ok(true);
+            }
+        }
+
+        let minter =
+            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
+                .minter(&deps.querier)?;
+
+        if minter.minter.as_deref() == Some(address.as_str()) {
+            return Ok(true);
+        }
+
+        let contract_info = deps.querier.query_wasm_contract_info(contract_address)?;
+
+        Ok(contract_info.admin.as_deref() == Some(address.as_str()))
+    }
+
+    /// Checks that `sender` owns the token, either directly or through an escrowed listing.
+    /// Returns whether the marketplace holds the token in escrow.
+    fn assert_token_owner(
+        deps: Deps,
+        contract_address: &Addr,
+        token_id: &str,
+        sender: &Addr,
+    ) -> Result<bool, ContractError> {
+        let sale = SALES.may_load(
+            deps.storage,
+            (contract_address.clone(), token_id.to_string()),
+        )?;
+
+        if let Some(sale) = sale.filter(|sale| sale.is_escrowed) {
+            if sale.owner_address != *sender {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            return Ok(true);
+        }
+
+        let owner =
+            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData)
+                .owner_of(&deps.querier, token_id.to_string(), false)?;
+
+        if owner.owner != sender.to_string() {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        Ok(false)
+    }
+
+    /// Checks that the marketplace may transfer the token, either through an approval for the
+    /// token or as a non-expired operator of `owner`.
+    fn assert_approved(
+        deps: Deps,
+        env: &Env,
+        contract_address: &Addr,
+        token_id: &str,
+        owner: &Addr,
+    ) -> Result<(), ContractError> {
+        let cw721 =
+            Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData);
+
+        let approval = cw721.approval(
+            &deps.querier,
+            token_id.to_string(),
+            env.contract.address.to_string(),
+            Some(false),
+        );
+
+        if approval.is_ok() {
+            return Ok(());
+        }
+
+        let operator = cw721.operator(
+            &deps.querier,
+            owner.to_string(),
+            env.contract.address.to_string(),
+            false,
+        );
+
+        if operator.is_err() {
+            return Err(ContractError::NotApproved {});
+        }
+
+        Ok(())
+    }
+
+    fn transfer_nft_msg(
+        contract_address: &Addr,
+        token_id: &str,
+        recipient: &Addr,
+    ) -> StdResult<CosmosMsg> {
+        Cw721Contract::<Empty, Empty>(contract_address.clone(), PhantomData, PhantomData).call(
+            ExecuteMsg::<Empty>::TransferNft {
+                recipient: recipient.to_string(),
+                token_id: token_id.to_string(),
+            },
+        )
+    }
+
+    fn assert_not_paused(storage: &dyn Storage, env: &Env) -> Result<(), ContractError> {
+        if is_paused(storage, &env.block)? {
+            return Err(ContractError::MarketplacePaused {});
+        }
+
+        Ok(())
+    }
+
+    /// Loads a registered collection with any matured royalty applied, failing if it is paused
+    /// or deregistered.
+    fn load_active_collection(
+        storage: &dyn Storage,
+        env: &Env,
+        contract_address: &Addr,
+    ) -> Result<Collection, ContractError> {
+        let mut collection = COLLECTIONS
+            .load(storage, contract_address.clone())
+            .map_err(|_| ContractError::CollectionNotExist {})?;
+
+        if collection.is_deregistered {
+            return Err(ContractError::CollectionDeregistered {
+                contract_address: contract_address.to_string(),
+            });
+        }
+
+        if collection.is_paused {
+            return Err(ContractError::CollectionPaused {
+                contract_address: contract_address.to_string(),
+            });
+        }
+
+        collection.apply_pending_royalty(env.block.time);
+
+        Ok(collection)
+    }
+
+    /// Splits `funds` between the taker, the royalty recipient and the seller, then transfers
+    /// the token to `recipient`. The transfer is checked in `verify_transfer`. The returned
+    /// event describes a listing sale, callers filling other orders adjust its source.
+    fn settle(
+        storage: &mut dyn Storage,
+        contract_address: &Addr,
+        collection: &Collection,
+        token_id: &str,
+        funds: &Coin,
+        seller: &Addr,
+        recipient: &Addr,
+    ) -> Result<(Vec<SubMsg>, ListingSold), ContractError> {
+        let config = CONFIG.load(storage)?;
+        let split = split_sale_price(&config, collection, funds)?;
+
+        let mut messages: Vec<SubMsg> = Vec::new();
+
+        if !split.taker_fee.is_zero() {
+            messages.extend(
+                pay_out(
+                    storage,
+                    &config,
+                    &config.taker_address,
+                    Coin::new(split.taker_fee.u128(), &funds.denom),
+                )?
+                .map(SubMsg::new),
+            );
+        }
+
+        if let Some(royalty_recipient) = &split.royalty_recipient {
+            messages.extend(
+                pay_out(
+                    storage,
+                    &config,
+                    royalty_recipient,
+                    Coin::new(split.royalty.u128(), &funds.denom),
+                )?
+                .map(SubMsg::new),
+            );
+        }
+
+        if !split.seller_proceeds.is_zero() {
+            messages.extend(
+                pay_out(
+                    storage,
+                    &config,
+                    seller,
+                    Coin::new(split.seller_proceeds.u128(), &funds.denom),
+                )?
+                .map(SubMsg::new),
+            );
+        }
+
+        let transfer_id = NEXT_TRANSFER_ID.may_load(storage)?.unwrap_or_default();
+        NEXT_TRANSFER_ID.save(storage, &(transfer_id + 1))?;
+        PENDING_TRANSFERS.save(
+            storage,
+            transfer_id,
+            &PendingTransfer {
+                contract_address: contract_address.clone(),
+                token_id: token_id.to_string(),
+                seller: seller.clone(),
+                recipient: recipient.clone(),
+                price: funds.clone(),
+            },
+        )?;
+
+        messages.push(SubMsg::reply_on_success(
+            transfer_nft_msg(contract_address, token_id, recipient)?,
+            transfer_id,
+        ));
+
+        let sold = ListingSold {
+            contract_address: contract_address.clone(),
+            token_id: token_id.to_string(),
+            seller: seller.clone(),
+            buyer: recipient.clone(),
+            price: funds.clone(),
+            taker_fee: split.taker_fee,
+            royalty: split.royalty,
+            royalty_recipient: split.royalty_recipient,
+            source: SaleSource::Listing,
+            nonce: None,
+        };
+
+        Ok((messages, sold))
+    }
+
+    /// Confirms that a settled token reached its buyer and records the trade. A collection that
+    /// accepts `TransferNft` without moving the token fails the whole sale.
+    pub fn verify_transfer(
+        deps: DepsMut,
+        env: Env,
+        transfer_id: u64,
+    ) -> Result<Response, ContractError> {
+        let transfer = PENDING_TRANSFERS.load(deps.storage, transfer_id)?;
+        PENDING_TRANSFERS.remove(deps.storage, transfer_id);
+
+        let owner = Cw721Contract::<Empty, Empty>(
+            transfer.contract_address.clone(),
+            PhantomData,
+            PhantomData,
+        )
+        .owner_of(&deps.querier, transfer.token_id.clone(), false)?;
+
+        if owner.owner != transfer.recipient.to_string() {
+            return Err(ContractError::TransferNotCompleted {
+                contract_address: transfer.contract_address.to_string(),
+                token_id: transfer.token_id,
+            });
+        }
+
+        TRADES.save(
+            deps.storage,
+            (transfer.contract_address.clone(), transfer.token_id.clone()),
+            &Trade {
+                seller: transfer.seller.clone(),
+                buyer: transfer.recipient.clone(),
+                price: transfer.price.clone(),
+                finalized_at: env.block.time,
+            },
+        )?;
+
+        Ok(Response::new().add_event(
+            Event::new("finalize_trade")
+                .add_attribute("contract_address", transfer.contract_address)
+                .add_attribute("token_id", transfer.token_id)
+                .add_attribute("price", transfer.price.amount)
+                .add_attribute("from", transfer.seller)
+                .add_attribute("to", transfer.recipient),
+        ))
+    }
+
+    /// Sends `funds` to `recipient`, or credits them for a later `Withdraw` under pull payments.
+    fn pay_out(
+        storage: &mut dyn Storage,
+        config: &Config,
+        recipient: &Addr,
+        funds: Coin,
+    ) -> StdResult<Option<CosmosMsg>> {
+        if config.pull_payments {
+            BALANCES.update(
+                storage,
+                (recipient.clone(), &funds.denom),
+                |balance| -> StdResult<_> {
+                    Ok(balance.unwrap_or_default().checked_add(funds.amount)?)
+                },
+            )?;
+
+            return Ok(None);
+        }
+
+        Ok(Some(
+            BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![funds],
+            }
+            .into(),
+        ))
+    }
+
+    pub fn update_ownership(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        action: cw_ownable::Action,
+    ) -> Result<Response, ContractError> {
+        let ownership = cw_ownable::update_ownership(deps, &env.block, &info.sender, action)?;
+
+        Ok(Response::new().add_event(
+            OwnershipUpdated {
+                owner: ownership.owner,
+                pending_owner: ownership.pending_owner,
+                pending_expiry: ownership.pending_expiry.map(|expiry| expiry.to_string()),
+            }
+            .to_event(),
+        ))
+    }
+}
+
+pub mod query {
+    use cosmwasm_std::{Addr, Binary, Coin, Deps, Env, Order, StdResult, Uint64};
+    use cw_storage_plus::Bound;
+
+    use super::{is_paused, split_sale_price};
+    use crate::{
+        msg::{
+            AllowedCodeIdsInfo, BalancesInfo, BlockedAddress, BlockedAddressesInfo, BlockedToken,
+            BlockedTokensInfo, CollectionMetadataInfo, CollectionsInfo, CollectionsMetadataInfo,
+            ConfigInfo, DeregistrationInfo, GroupBuyContributionInfo, GroupBuyInfo, HookInfo,
+            HooksInfo, LastTradeInfo, LaunchpadInfo, LaunchpadMintsInfo, ListedSale,
+            ListedSalesInfo, ListingStatus, MerkleClaimsInfo, OffersInfo, OrderNonceInfo,
+            PendingCollectionInfo, PendingCollectionsInfo, RegisteredCollection,
+            RegisteredCollectionsInfo, RoleMembersInfo, RolesInfo, SaleSimulationInfo, SalesInfo,
+            SealedAuctionInfo, SealedBidInfo, TakerFeeInfo,
+        },
+        state::{
+            CollectionMetadata, Role, TokenId, ALLOWED_CODE_IDS, BALANCES, BLOCKED_ADDRESSES,
+            BLOCKED_TOKENS, COLLECTIONS, COLLECTION_METADATA, COLLECTION_OFFERS, CONFIG,
+            DEREGISTRATIONS, GROUP_BUYS, GROUP_BUY_CONTRIBUTIONS, HOOKS, LAUNCHPADS,
+            LAUNCHPAD_ALLOWLISTS, LAUNCHPAD_MINTS, MERKLE_CLAIMS, MIN_NONCES, PAUSE,
+            PENDING_COLLECTIONS, ROLES, SALES, SEALED_AUCTIONS, SEALED_BIDS, TRADES, USED_NONCES,
+        },
+    };
+
+    const MAX_LIMIT: u64 = 100;
+
+    pub fn get_sale(
+        deps: Deps,
+        contract_address: String,
+        token_id: String,
+    ) -> StdResult<SalesInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let sale = SALES.load(deps.storage, (contract_address, token_id))?;
+
+        Ok(SalesInfo { sales: vec![sale] })
+    }
+
+    pub fn get_sales(
+        deps: Deps,
+        env: Env,
+        start_after: Option<(String, TokenId)>,
+        limit: Option<u32>,
+        status: Option<ListingStatus>,
+    ) -> StdResult<ListedSalesInfo> {
+        let start_after = start_after
+            .map(|(contract_address, token_id)| {
+                deps.api
+                    .addr_validate(&contract_address)
+                    .map(|contract_address| (contract_address, token_id))
+            })
+            .transpose()?;
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+        let status = status.unwrap_or(ListingStatus::All);
+
+        let sales = SALES
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .filter(|item| match (item, &status) {
+                (Ok((_, sale)), ListingStatus::Live) => sale.is_live(env.block.time),
+                (Ok((_, sale)), ListingStatus::Upcoming) => !sale.is_live(env.block.time),
+                _ => true,
+            })
+            .take(limit)
+            .map(|item| {
+                item.map(|((contract_address, token_id), sale)| ListedSale {
+                    contract_address,
+                    token_id,
+                    sale,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ListedSalesInfo { sales })
+    }
+
+    pub fn get_collection(
+        deps: Deps,
+        env: Env,
+        contract_address: String,
+    ) -> StdResult<CollectionsInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let mut collection = COLLECTIONS.load(deps.storage, contract_address)?;
+        collection.apply_pending_royalty(env.block.time);
+
+        Ok(CollectionsInfo {
+            collections: vec![collection],
+        })
+    }
+
+    pub fn get_collections(
+        deps: Deps,
+        env: Env,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RegisteredCollectionsInfo> {
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+
+        let collections = COLLECTIONS
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| {
+                item.map(|(contract_address, mut collection)| {
+                    collection.apply_pending_royalty(env.block.time);
+                    RegisteredCollection {
+                        contract_address,
+                        collection,
+                    }
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(RegisteredCollectionsInfo { collections })
+    }
+
+    pub fn get_taker_fee(deps: Deps) -> StdResult<TakerFeeInfo> {
+        let config = CONFIG.load(deps.storage)?;
+
+        Ok(TakerFeeInfo {
+            taker_fee: Uint64::from(config.taker_fee),
+        })
+    }
+
+    pub fn get_config(deps: Deps, env: Env) -> StdResult<ConfigInfo> {
+        let config = CONFIG.load(deps.storage)?;
+
+        Ok(ConfigInfo {
+            config,
+            is_paused: is_paused(deps.storage, &env.block)?,
+            pause: PAUSE.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn get_blocked_addresses(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<BlockedAddressesInfo> {
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+
+        let addresses = BLOCKED_ADDRESSES
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(address, entry)| BlockedAddress { address, entry }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(BlockedAddressesInfo { addresses })
+    }
+
+    pub fn get_blocked_tokens(
+        deps: Deps,
+        contract_address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<BlockedTokensInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+
+        let tokens = BLOCKED_TOKENS
+            .prefix(contract_address.clone())
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| {
+                item.map(|(token_id, entry)| BlockedToken {
+                    contract_address: contract_address.clone(),
+                    token_id,
+                    entry,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(BlockedTokensInfo { tokens })
+    }
+
+    pub fn get_pending_collections(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<PendingCollectionsInfo> {
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+
+        let collections = PENDING_COLLECTIONS
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| {
+                item.map(|(contract_address, pending)| PendingCollectionInfo {
+                    contract_address,
+                    pending,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(PendingCollectionsInfo { collections })
+    }
+
+    pub fn get_hooks(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<HooksInfo> {
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+
+        let hooks = HOOKS
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| {
+                item.map(|(contract_address, hook)| HookInfo {
+                    contract_address,
+                    hook,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(HooksInfo { hooks })
+    }
+
+    pub fn get_allowed_code_ids(
+        deps: Deps,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<AllowedCodeIdsInfo> {
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+
+        let code_ids = ALLOWED_CODE_IDS
+            .keys(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(AllowedCodeIdsInfo { code_ids })
+    }
+
+    pub fn get_collection_metadata(
+        deps: Deps,
+        env: Env,
+        contract_address: String,
+    ) -> StdResult<CollectionMetadataInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let metadata = COLLECTION_METADATA.load(deps.storage, contract_address.clone())?;
+
+        collection_metadata_info(deps, &env, contract_address, metadata)
+    }
+
+    pub fn search_collections(
+        deps: Deps,
+        env: Env,
+        category: Option<String>,
+        is_verified: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<CollectionsMetadataInfo> {
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+
+        let collections = COLLECTION_METADATA
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .filter(|item| match item {
+                Ok((_, metadata)) => {
+                    category.as_ref().map_or(true, |category| {
+                        metadata.category.as_ref() == Some(category)
+                    }) && is_verified
+                        .map_or(true, |is_verified| metadata.is_verified == is_verified)
+                }
+                Err(_) => true,
+            })
+            .take(limit)
+            .map(|item| {
+                let (contract_address, metadata) = item?;
+                collection_metadata_info(deps, &env, contract_address, metadata)
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(CollectionsMetadataInfo { collections })
+    }
+
+    fn collection_metadata_info(
+        deps: Deps,
+        env: &Env,
+        contract_address: Addr,
+        metadata: CollectionMetadata,
+    ) -> StdResult<CollectionMetadataInfo> {
+        let mut collection = COLLECTIONS.load(deps.storage, contract_address.clone())?;
+        collection.apply_pending_royalty(env.block.time);
+
+        Ok(CollectionMetadataInfo {
+            contract_address,
+            collection,
+            metadata,
+        })
+    }
+
+    pub fn get_balances(deps: Deps, address: String) -> StdResult<BalancesInfo> {
+        let address = deps.api.addr_validate(&address)?;
+
+        let balances = BALANCES
+            .prefix(address)
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(BalancesInfo { balances })
+    }
+
+    pub fn get_last_trade(
+        deps: Deps,
+        contract_address: String,
+        token_id: String,
+    ) -> StdResult<LastTradeInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+
+        Ok(LastTradeInfo {
+            trade: TRADES.may_load(deps.storage, (contract_address, token_id))?,
+        })
+    }
+
+    pub fn get_sealed_auction(
+        deps: Deps,
+        contract_address: String,
+        token_id: String,
+    ) -> StdResult<SealedAuctionInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+
+        Ok(SealedAuctionInfo {
+            auction: SEALED_AUCTIONS.load(deps.storage, (contract_address, token_id))?,
+        })
+    }
+
+    pub fn get_sealed_bid(
+        deps: Deps,
+        contract_address: String,
+        token_id: String,
+        bidder: String,
+    ) -> StdResult<SealedBidInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let bidder = deps.api.addr_validate(&bidder)?;
+
+        Ok(SealedBidInfo {
+            bid: SEALED_BIDS.may_load(deps.storage, (contract_address, token_id, bidder))?,
+        })
+    }
+
+    pub fn get_group_buy(deps: Deps, group_buy_id: u64) -> StdResult<GroupBuyInfo> {
+        Ok(GroupBuyInfo {
+            group_buy: GROUP_BUYS.load(deps.storage, group_buy_id)?,
+        })
+    }
+
+    pub fn get_group_buy_contribution(
+        deps: Deps,
+        group_buy_id: u64,
+        contributor: String,
+    ) -> StdResult<GroupBuyContributionInfo> {
+        let contributor = deps.api.addr_validate(&contributor)?;
+
+        Ok(GroupBuyContributionInfo {
+            amount: GROUP_BUY_CONTRIBUTIONS
+                .may_load(deps.storage, (group_buy_id, contributor))?
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn get_launchpad(
+        deps: Deps,
+        env: Env,
+        contract_address: String,
+    ) -> StdResult<LaunchpadInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let launchpad = LAUNCHPADS.load(deps.storage, contract_address)?;
+        let active_phase = launchpad
+            .phases
+            .iter()
+            .position(|phase| phase.starts_at <= env.block.time && env.block.time < phase.ends_at)
+            .map(|phase_id| phase_id as u32);
+
+        Ok(LaunchpadInfo {
+            launchpad,
+            active_phase,
+        })
+    }
+
+    pub fn get_launchpad_mints(
+        deps: Deps,
+        contract_address: String,
+        phase_id: u32,
+        address: String,
+    ) -> StdResult<LaunchpadMintsInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let address = deps.api.addr_validate(&address)?;
+        let key = (contract_address, phase_id, address);
+
+        Ok(LaunchpadMintsInfo {
+            minted: LAUNCHPAD_MINTS
+                .may_load(deps.storage, key.clone())?
+                .unwrap_or_default(),
+            is_allowlisted: LAUNCHPAD_ALLOWLISTS.has(deps.storage, key),
+        })
+    }
+
+    pub fn get_merkle_claims(
+        deps: Deps,
+        merkle_root: Binary,
+        address: String,
+    ) -> StdResult<MerkleClaimsInfo> {
+        let address = deps.api.addr_validate(&address)?;
+
+        Ok(MerkleClaimsInfo {
+            claimed: MERKLE_CLAIMS
+                .may_load(deps.storage, (merkle_root.as_slice(), address))?
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn get_deregistration(
+        deps: Deps,
+        contract_address: String,
+    ) -> StdResult<DeregistrationInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+
+        Ok(DeregistrationInfo {
+            deregistration: DEREGISTRATIONS.may_load(deps.storage, contract_address.clone())?,
+            remaining_sales: SALES
+                .prefix(contract_address.clone())
+                .keys_raw(deps.storage, None, None, Order::Ascending)
+                .count() as u64,
+            remaining_offers: COLLECTION_OFFERS
+                .prefix(contract_address.clone())
+                .keys_raw(deps.storage, None, None, Order::Ascending)
+                .count() as u64,
+            remaining_auctions: SEALED_AUCTIONS
+                .prefix(contract_address)
+                .keys_raw(deps.storage, None, None, Order::Ascending)
+                .count() as u64,
+        })
+    }
+
+    pub fn simulate_sale(
+        deps: Deps,
+        env: Env,
+        contract_address: String,
+        price: Coin,
+    ) -> StdResult<SaleSimulationInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let mut collection = COLLECTIONS.load(deps.storage, contract_address)?;
+        collection.apply_pending_royalty(env.block.time);
+
+        split_sale_price(&CONFIG.load(deps.storage)?, &collection, &price)
+    }
+
+    pub fn get_order_nonce(deps: Deps, seller: String, nonce: u64) -> StdResult<OrderNonceInfo> {
+        let seller = deps.api.addr_validate(&seller)?;
+        let min_nonce = MIN_NONCES
+            .may_load(deps.storage, seller.clone())?
+            .unwrap_or_default();
+
+        Ok(OrderNonceInfo {
+            min_nonce,
+            is_used: USED_NONCES.has(deps.storage, (seller, nonce)),
+        })
+    }
+
+    pub fn get_collection_offers(
+        deps: Deps,
+        contract_address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<OffersInfo> {
+        let contract_address = deps.api.addr_validate(&contract_address)?;
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+
+        let offers = COLLECTION_OFFERS
+            .prefix(contract_address)
+            .range(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .map(|item| item.map(|(_, offer)| offer))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(OffersInfo { offers })
+    }
+
+    pub fn get_role_members(
+        deps: Deps,
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<RoleMembersInfo> {
+        let start_after = start_after
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?;
+        let limit = limit.map_or(MAX_LIMIT, u64::from).min(MAX_LIMIT) as usize;
+
+        let members = ROLES
+            .prefix(role.as_str())
+            .keys(
+                deps.storage,
+                start_after.map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(RoleMembersInfo { members })
+    }
+
+    pub fn get_roles(deps: Deps, address: String) -> StdResult<RolesInfo> {
+        let address = deps.api.addr_validate(&address)?;
+
+        let roles = [
+            Role::FeeAdmin,
+            Role::CollectionCurator,
+            Role::Moderator,
+            Role::Pauser,
+        ]
+        .into_iter()
+        .filter(|role| ROLES.has(deps.storage, (role.as_str(), address.clone())))
+        .collect();
+
+        Ok(RolesInfo { roles })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::marker::PhantomData;
+
+    use bech32::{ToBase32, Variant};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::{coin, coins, to_vec, Addr, Coin, Empty, StdError, Uint128, Uint64};
+    use cw721_rewards::helpers::Cw721Contract;
+    use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+    use cw_ownable::OwnershipError;
+    use cw_storage_plus::Item;
+    use cw_utils::PaymentError;
+    use k256::ecdsa::{signature::DigestSigner, Signature, SigningKey};
+    use ripemd::Ripemd160;
+    use sha2::{Digest, Sha256};
+
+    use super::*;
+    use crate::events::{
+        AddressBlocked, ListingCancelled, ListingSold, MarketplaceEvent, MarketplacePaused,
+        SaleSource, TokenBlocked,
+    };
+    use crate::helpers::MarketplaceContract;
+    use crate::msg::{
+        AllowedCodeIdsInfo, BalancesInfo, BlockedAddressesInfo, BlockedTokensInfo,
+        CollectionMetadataInfo, CollectionsInfo, CollectionsMetadataInfo, ConfigInfo,
+        DeregistrationInfo, HooksInfo, LastTradeInfo, LaunchpadMintsInfo, LaunchpadTerms,
+        ListedSalesInfo, ListingStatus, MarketplaceHookExecuteMsg, MarketplaceHookMsg,
+        MerkleAllowlistTarget, MerkleClaimsInfo, MerkleProof, OffersInfo, OrderNonceInfo,
+        OrderSignDoc, PendingCollectionsInfo, ReceiveNftMsg, RegisteredCollectionsInfo,
+        RoleMembersInfo, RolesInfo, SalesInfo, SealedBidCommitment, SignedOrder,
+    };
+    use crate::state::{
+        AuctionPricing, CollectionMetadata, MerkleAllowlist, MintPhase, Role, Sale,
+        SealedAuctionTerms, COLLECTIONS, SALES,
+    };
+
+    const ADMIN: &str = "admin";
+    const SELLER: &str = "seller";
+    const BUYER: &str = "buyer";
+    const TAKER: &str = "taker";
+    const ARTIST: &str = "artist";
+    const DENOM: &str = "aconst";
+
+    fn marketplace_contract() -> Box<dyn Contract<Empty>> {
+        Box::new(
+            ContractWrapper::new(execute, instantiate, query)
+                .with_reply(reply)
+                .with_migrate(migrate),
+        )
+    }
+
+    fn cw721_contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(
+            cw721_rewards::entry::execute,
+            cw721_rewards::entry::instantiate,
+            cw721_rewards::entry::query,
+        ))
+    }
+
+    fn noop_execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: cw721::Cw721ExecuteMsg,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn noop_instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    /// Reports `SELLER` as the owner and approves every spender, whatever was transferred.
+    fn stuck_query(_deps: Deps, _env: Env, msg: cw721::Cw721QueryMsg) -> StdResult<Binary> {
+        match msg {
+            cw721::Cw721QueryMsg::OwnerOf { .. } => to_binary(&cw721::OwnerOfResponse {
+                owner: SELLER.to_string(),
+                approvals: vec![],
+            }),
+            cw721::Cw721QueryMsg::Approval { spender, .. } => to_binary(&cw721::ApprovalResponse {
+                approval: cw721::Approval {
+                    spender,
+                    expires: cw721::Expiration::Never {},
+                },
+            }),
+            cw721::Cw721QueryMsg::ContractInfo {} => to_binary(&cw721::ContractInfoResponse {
+                name: "Stuck".to_string(),
+                symbol: "STUCK".to_string(),
+            }),
+            cw721::Cw721QueryMsg::NumTokens {} => to_binary(&cw721::NumTokensResponse { count: 1 }),
+            _ => Err(StdError::generic_err("unsupported query")),
+        }
+    }
+
+    /// A non-compliant cw721 that accepts `TransferNft` without moving the token.
+    fn stuck_cw721_contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(
+            noop_execute,
+            noop_instantiate,
+            stuck_query,
+        ))
+    }
+
+    const HOOK_MSGS: Item<Vec<MarketplaceHookMsg>> = Item::new("hook_msgs");
+
+    /// A hook that stores every notification it receives.
+    fn recording_hook_execute(
+        deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        msg: MarketplaceHookExecuteMsg,
+    ) -> StdResult<Response> {
+        let MarketplaceHookExecuteMsg::MarketplaceHook(msg) = msg;
+        let mut msgs = HOOK_MSGS.may_load(deps.storage)?.unwrap_or_default();
+        msgs.push(msg);
+        HOOK_MSGS.save(deps.storage, &msgs)?;
+
+        Ok(Response::new())
+    }
+
+    fn failing_hook_execute(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: MarketplaceHookExecuteMsg,
+    ) -> StdResult<Response> {
+        Err(StdError::generic_err("hook rejected"))
+    }
+
+    fn hook_query(deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_binary(&HOOK_MSGS.may_load(deps.storage)?.unwrap_or_default())
+    }
+
+    fn recording_hook_contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(
+            recording_hook_execute,
+            noop_instantiate,
+            hook_query,
+        ))
+    }
+
+    fn failing_hook_contract() -> Box<dyn Contract<Empty>> {
+        Box::new(ContractWrapper::new(
+            failing_hook_execute,
+            noop_instantiate,
+            hook_query,
+        ))
+    }
+
+    struct Suite {
+        app: App,
+        marketplace: Addr,
+        nft: Addr,
+        nft_code_id: u64,
+    }
+
+    impl Suite {
+        /// Marketplace with a 2% taker fee and a cw721 collection registered at 10% royalty.
+        fn new() -> Self {
+            let mut app = App::new(|router, _, storage| {
+                router
+                    .bank
+                    .init_balance(storage, &Addr::unchecked(BUYER), coins(1_000_000, DENOM))
+                    .unwrap();
+            });
+
+            let marketplace_id = app.store_code(marketplace_contract());
+            let nft_id = app.store_code(cw721_contract());
+
+            let marketplace = app
+                .instantiate_contract(
+                    marketplace_id,
+                    Addr::unchecked(ADMIN),
+                    &InstantiateMsg {
+                        taker_fee: Uint64::new(2),
+                        native_denom: DENOM.to_string(),
+                        taker_address: TAKER.to_string(),
+                        registration_bond: Uint128::new(100),
+                        pull_payments: false,
+                    },
+                    &[],
+                    "marketplace",
+                    Some(ADMIN.to_string()),
+                )
+                .unwrap();
+
+            let mut suite = Suite {
+                app,
+                marketplace,
+                nft: Addr::unchecked(""),
+                nft_code_id: nft_id,
+            };
+            suite.nft = suite.instantiate_collection(ADMIN);
+
+            suite
+                .execute(
+                    ADMIN,
+                    ExecuteMsg::RegisterCollection {
+                        contract_address: suite.nft.to_string(),
+                        royalty_percentage: Some(10),
+                        royalty_payment_address: Some(ARTIST.to_string()),
+                    },
+                    &[],
+                )
+                .unwrap();
+
+            suite
+        }
+
+        fn instantiate_collection(&mut self, minter: &str) -> Addr {
+            self.app
+                .instantiate_contract(
+                    self.nft_code_id,
+                    Addr::unchecked(minter),
+                    &cw721_rewards::InstantiateMsg {
+                        name: "Collection".to_string(),
+                        symbol: "COL".to_string(),
+                        minter: minter.to_string(),
+                    },
+                    &[],
+                    "collection",
+                    None,
+                )
+                .unwrap()
+        }
+
+        fn execute(
+            &mut self,
+            sender: &str,
+            msg: ExecuteMsg,
+            funds: &[Coin],
+        ) -> anyhow::Result<AppResponse> {
+            self.app.execute_contract(
+                Addr::unchecked(sender),
+                self.marketplace.clone(),
+                &msg,
+                funds,
+            )
+        }
+
+        fn execute_err(&mut self, sender: &str, msg: ExecuteMsg, funds: &[Coin]) -> ContractError {
+            self.execute(sender, msg, funds)
+                .unwrap_err()
+                .downcast()
+                .unwrap()
+        }
+
+        fn mint(&mut self, owner: &str, token_id: &str) {
+            self.app
+                .execute_contract(
+                    Addr::unchecked(ADMIN),
+                    self.nft.clone(),
+                    &cw721_rewards::ExecuteMsg::<Empty>::Mint {
+                        token_id: token_id.to_string(),
+                        owner: owner.to_string(),
+                        token_uri: None,
+                        extension: Empty {},
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        fn approve(&mut self, owner: &str, token_id: &str) {
+            self.app
+                .execute_contract(
+                    Addr::unchecked(owner),
+                    self.nft.clone(),
+                    &cw721_rewards::ExecuteMsg::<Empty>::Approve {
+                        spender: self.marketplace.to_string(),
+                        token_id: token_id.to_string(),
+                        expires: None,
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        /// Mints `token_id` to the seller, approves the marketplace and lists it.
+        fn list(&mut self, token_id: &str, amount: u128) {
+            self.mint(SELLER, token_id);
+            self.approve(SELLER, token_id);
+            self.execute(
+                SELLER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: self.nft.to_string(),
+                    token_id: token_id.to_string(),
+                    price: coin(amount, DENOM),
+                    starts_at: None,
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        /// Mints `token_id` to the seller and lists it by sending it into escrow.
+        fn list_escrowed(&mut self, token_id: &str, amount: u128) {
+            self.mint(SELLER, token_id);
+            self.app
+                .execute_contract(
+                    Addr::unchecked(SELLER),
+                    self.nft.clone(),
+                    &cw721_rewards::ExecuteMsg::<Empty>::SendNft {
+                        contract: self.marketplace.to_string(),
+                        token_id: token_id.to_string(),
+                        msg: to_binary(&ReceiveNftMsg::ListForSale {
+                            price: coin(amount, DENOM),
+                            starts_at: None,
+                        })
+                        .unwrap(),
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        fn query_sale(&self, token_id: &str) -> StdResult<SalesInfo> {
+            self.app.wrap().query_wasm_smart(
+                self.marketplace.clone(),
+                &QueryMsg::GetSale {
+                    contract_address: self.nft.to_string(),
+                    token_id: token_id.to_string(),
+                },
+            )
+        }
+
+        fn query_collection(&self) -> CollectionsInfo {
+            self.app
+                .wrap()
+                .query_wasm_smart(
+                    self.marketplace.clone(),
+                    &QueryMsg::GetCollection {
+                        contract_address: self.nft.to_string(),
+                    },
+                )
+                .unwrap()
+        }
+
+        fn query_config(&self) -> ConfigInfo {
+            self.app
+                .wrap()
+                .query_wasm_smart(self.marketplace.clone(), &QueryMsg::GetConfig {})
+                .unwrap()
+        }
+
+        fn balance(&self, address: &str) -> Uint128 {
+            self.app
+                .wrap()
+                .query_balance(address, DENOM)
+                .unwrap()
+                .amount
+        }
+
+        fn nft_owner(&self, token_id: &str) -> String {
+            Cw721Contract::<Empty, Empty>(self.nft.clone(), PhantomData, PhantomData)
+                .owner_of(&self.app.wrap(), token_id, false)
+                .unwrap()
+                .owner
+        }
+    }
+
+    /// A secp256k1 key with a matching bech32 address, for signing orders off-chain.
+    struct OrderSigner {
+        key: SigningKey,
+        address: String,
+        pubkey: Binary,
+    }
+
+    impl OrderSigner {
+        fn new() -> Self {
+            let key = SigningKey::from_bytes(&[7u8; 32]).unwrap();
+            let pubkey = key
+                .verifying_key()
+                .to_encoded_point(true)
+                .as_bytes()
+                .to_vec();
+            let account = Ripemd160::digest(Sha256::digest(&pubkey));
+            let address = bech32::encode("archway", account.to_base32(), Variant::Bech32).unwrap();
+
+            OrderSigner {
+                key,
+                address,
+                pubkey: Binary::from(pubkey),
+            }
+        }
+
+        fn order(&self, suite: &Suite, token_id: &str, nonce: u64) -> SignedOrder {
+            SignedOrder {
+                seller: self.address.clone(),
+                contract_address: suite.nft.to_string(),
+                token_id: token_id.to_string(),
+                price: coin(1_000, DENOM),
+                expires_at: suite.app.block_info().time.plus_seconds(3_600),
+                nonce,
+            }
+        }
+
+        fn sign(&self, suite: &Suite, order: &SignedOrder) -> Binary {
+            let sign_doc = OrderSignDoc {
+                chain_id: suite.app.block_info().chain_id,
+                marketplace: suite.marketplace.to_string(),
+                order: order.clone(),
+            };
+            let signature: Signature = self
+                .key
+                .sign_digest(Sha256::new().chain_update(to_vec(&sign_doc).unwrap()));
+
+            Binary::from(signature.as_ref())
+        }
+
+        fn fill_msg(&self, suite: &Suite, order: SignedOrder) -> ExecuteMsg {
+            ExecuteMsg::FillSignedOrder {
+                signature: self.sign(suite, &order),
+                order,
+                pubkey: self.pubkey.clone(),
+            }
+        }
+    }
+
+    #[test]
+    fn list_approved_token() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let sales = suite.query_sale("1").unwrap();
+        assert_eq!(
+            sales.sales,
+            vec![crate::state::Sale {
+                owner_address: Addr::unchecked(SELLER),
+                price: coin(1_000, DENOM),
+                is_escrowed: false,
+                version: 1,
+                starts_at: None,
+                merkle_allowlist: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn list_without_approval_fails() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::UpdateSale {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                price: coin(1_000, DENOM),
+                starts_at: None,
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::NotApproved {});
+    }
+
+    #[test]
+    fn operator_approval_allows_listing_until_revoked() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+        suite.mint(SELLER, "2");
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(SELLER),
+                suite.nft.clone(),
+                &cw721_rewards::ExecuteMsg::<Empty>::ApproveAll {
+                    operator: suite.marketplace.to_string(),
+                    expires: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        for token_id in ["1", "2"] {
+            suite
+                .execute(
+                    SELLER,
+                    ExecuteMsg::UpdateSale {
+                        contract_address: suite.nft.to_string(),
+                        token_id: token_id.to_string(),
+                        price: coin(1_000, DENOM),
+                        starts_at: None,
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+        assert_eq!(suite.nft_owner("1"), BUYER);
+
+        // revoking the operator invalidates the remaining listing at buy time
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(SELLER),
+                suite.nft.clone(),
+                &cw721_rewards::ExecuteMsg::<Empty>::RevokeAll {
+                    operator: suite.marketplace.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "2".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, ContractError::NotApproved {});
+    }
+
+    #[test]
+    fn expired_or_foreign_operator_approval_is_rejected() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+        suite.mint(SELLER, "2");
+        let approve_all = |operator: &Addr, expires: cw721::Expiration| cw721_rewards::ExecuteMsg::<
+            Empty,
+        >::ApproveAll {
+            operator: operator.to_string(),
+            expires: Some(expires),
+        };
+        let update_sale = |token_id: &str| ExecuteMsg::UpdateSale {
+            contract_address: suite.nft.to_string(),
+            token_id: token_id.to_string(),
+            price: coin(1_000, DENOM),
+            starts_at: None,
+        };
+        let (list_1, list_2) = (update_sale("1"), update_sale("2"));
+
+        // an operator other than the marketplace does not count
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(SELLER),
+                suite.nft.clone(),
+                &approve_all(&Addr::unchecked(BUYER), cw721::Expiration::Never {}),
+                &[],
+            )
+            .unwrap();
+        let err = suite.execute_err(SELLER, list_1.clone(), &[]);
+        assert_eq!(err, ContractError::NotApproved {});
+
+        let expires_at = suite.app.block_info().height + 5;
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(SELLER),
+                suite.nft.clone(),
+                &approve_all(&suite.marketplace, cw721::Expiration::AtHeight(expires_at)),
+                &[],
+            )
+            .unwrap();
+        suite.execute(SELLER, list_1, &[]).unwrap();
+
+        // once the operator approval expires neither buying nor listing goes through
+        suite.app.update_block(|block| block.height += 10);
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, ContractError::NotApproved {});
+        let err = suite.execute_err(SELLER, list_2, &[]);
+        assert_eq!(err, ContractError::NotApproved {});
+        assert_eq!(suite.nft_owner("1"), SELLER);
+    }
+
+    #[test]
+    fn list_by_non_owner_fails() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+        suite.approve(SELLER, "1");
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::UpdateSale {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                price: coin(1_000, DENOM),
+                starts_at: None,
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn list_with_unsupported_denom_fails() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+        suite.approve(SELLER, "1");
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::UpdateSale {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                price: coin(1_000, "uatom"),
+                starts_at: None,
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::DenomNotSupported {});
+    }
+
+    #[test]
+    fn buy_pays_taker_royalty_and_seller() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        assert_eq!(suite.balance(BUYER), Uint128::new(999_000));
+        assert_eq!(suite.balance(TAKER), Uint128::new(20));
+        assert_eq!(suite.balance(ARTIST), Uint128::new(100));
+        assert_eq!(suite.balance(SELLER), Uint128::new(880));
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::zero());
+        assert_eq!(suite.nft_owner("1"), BUYER);
+        assert!(suite.query_sale("1").is_err());
+    }
+
+    #[test]
+    fn buy_without_royalty_pays_taker_and_seller() {
+        let mut suite = Suite::new();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateCollection {
+                    contract_address: suite.nft.to_string(),
+                    royalty_percentage: Some(0),
+                    royalty_payment_address: None,
+                    is_paused: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite.list("1", 1_000);
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        assert_eq!(suite.balance(BUYER), Uint128::new(999_000));
+        assert_eq!(suite.balance(TAKER), Uint128::new(20));
+        assert_eq!(suite.balance(ARTIST), Uint128::zero());
+        assert_eq!(suite.balance(SELLER), Uint128::new(980));
+    }
+
+    #[test]
+    fn buy_rejects_changed_listing_terms() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        let listed = suite.query_sale("1").unwrap().sales.remove(0);
+
+        // the seller raises the price before the buy lands
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    price: coin(1_500, DENOM),
+                    starts_at: None,
+                },
+                &[],
+            )
+            .unwrap();
+        let repriced = suite.query_sale("1").unwrap().sales.remove(0);
+        assert!(repriced.version > listed.version);
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: Some(coin(1_000, DENOM)),
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(2_000, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::PriceChanged {
+                expected: coin(1_000, DENOM),
+                current: coin(1_500, DENOM),
+            }
+        );
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: Some(listed.version),
+                merkle_proof: None,
+            },
+            &coins(2_000, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::ListingVersionChanged {
+                expected: listed.version,
+                current: repriced.version,
+            }
+        );
+        assert_eq!(suite.nft_owner("1"), SELLER);
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: Some(coin(1_500, DENOM)),
+                    expected_version: Some(repriced.version),
+                    merkle_proof: None,
+                },
+                &coins(1_500, DENOM),
+            )
+            .unwrap();
+        assert_eq!(suite.nft_owner("1"), BUYER);
+    }
+
+    #[test]
+    fn scheduled_listing_is_buyable_from_start() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite.mint(SELLER, "2");
+        suite.approve(SELLER, "2");
+
+        let starts_at = suite.app.block_info().time.plus_seconds(3_600);
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "2".to_string(),
+                    price: coin(1_000, DENOM),
+                    starts_at: Some(starts_at),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let query_sales = |suite: &Suite, status: ListingStatus| -> ListedSalesInfo {
+            suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    suite.marketplace.clone(),
+                    &QueryMsg::GetSales {
+                        start_after: None,
+                        limit: None,
+                        status: Some(status),
+                    },
+                )
+                .unwrap()
+        };
+        assert_eq!(query_sales(&suite, ListingStatus::All).sales.len(), 2);
+        assert_eq!(
+            query_sales(&suite, ListingStatus::Live).sales[0].token_id,
+            "1"
+        );
+        let upcoming = query_sales(&suite, ListingStatus::Upcoming).sales;
+        assert_eq!(upcoming.len(), 1);
+        assert_eq!(upcoming[0].token_id, "2");
+        assert_eq!(upcoming[0].sale.starts_at, Some(starts_at));
+
+        // a filtered page resumes after its last key, skipping entries of the other status
+        let live: ListedSalesInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetSales {
+                    start_after: Some((suite.nft.to_string(), "1".to_string())),
+                    limit: Some(1),
+                    status: Some(ListingStatus::Live),
+                },
+            )
+            .unwrap();
+        assert!(live.sales.is_empty());
+
+        let buy = ExecuteMsg::Buy {
+            contract_address: suite.nft.to_string(),
+            token_id: "2".to_string(),
+            expected_price: None,
+            expected_version: None,
+            merkle_proof: None,
+        };
+        let err = suite.execute_err(BUYER, buy.clone(), &coins(1_000, DENOM));
+        assert_eq!(err, ContractError::ListingNotStarted { starts_at });
+
+        suite
+            .app
+            .update_block(|block| block.time = block.time.plus_seconds(3_600));
+        assert_eq!(query_sales(&suite, ListingStatus::Live).len(), 2);
+        assert!(query_sales(&suite, ListingStatus::Upcoming).is_empty());
+
+        suite.execute(BUYER, buy, &coins(1_000, DENOM)).unwrap();
+        assert_eq!(suite.nft_owner("2"), BUYER);
+    }
+
+    #[test]
+    fn buy_with_insufficient_funds_fails() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(999, DENOM),
+        );
+        assert_eq!(err, ContractError::InsufficientFunds {});
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+        assert_eq!(suite.nft_owner("1"), SELLER);
+    }
+
+    #[test]
+    fn buy_with_excess_funds_fails() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: Some(coin(1_000, DENOM)),
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_500, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::InvalidFunds {
+                expected: coin(1_000, DENOM),
+                received: coin(1_500, DENOM),
+            }
+        );
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+        assert_eq!(suite.nft_owner("1"), SELLER);
+    }
+
+    #[test]
+    fn update_collection_pauses_collection() {
+        let mut suite = Suite::new();
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::UpdateCollection {
+                contract_address: suite.nft.to_string(),
+                royalty_percentage: Some(10),
+                royalty_payment_address: Some(ARTIST.to_string()),
+                is_paused: Some(true),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+        assert!(!suite.query_collection().collections[0].is_paused);
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateCollection {
+                    contract_address: suite.nft.to_string(),
+                    royalty_percentage: None,
+                    royalty_payment_address: None,
+                    is_paused: Some(true),
+                },
+                &[],
+            )
+            .unwrap();
+        let collection = suite.query_collection().collections[0].clone();
+        assert!(collection.is_paused);
+        assert_eq!(collection.royalty_percentage, Some(10));
+        assert_eq!(
+            collection.royalty_payment_address,
+            Some(Addr::unchecked(ARTIST))
+        );
+    }
+
+    #[test]
+    fn admin_remove_sale() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::AdminRemoveSales {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::AdminRemoveSales {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert!(suite.query_sale("1").is_err());
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::AdminRemoveSales {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::SaleDoesNotExist {});
+    }
+
+    #[test]
+    fn seller_remove_sale() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::RemoveSale {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::RemoveSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert!(suite.query_sale("1").is_err());
+    }
+
+    #[test]
+    fn transfer_ownership() {
+        let mut suite = Suite::new();
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateOwnership(cw_ownable::Action::TransferOwnership {
+                    new_owner: SELLER.to_string(),
+                    expiry: None,
+                }),
+                &[],
+            )
+            .unwrap();
+
+        // the old owner keeps control until the transfer is accepted
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateTakerFee {
+                    taker_fee: Uint64::new(3),
+                },
+                &[],
+            )
+            .unwrap();
+
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateOwnership(cw_ownable::Action::AcceptOwnership),
+                &[],
+            )
+            .unwrap();
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::UpdateTakerFee {
+                taker_fee: Uint64::new(4),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateTakerFee {
+                    taker_fee: Uint64::new(5),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let taker_fee: crate::msg::TakerFeeInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(suite.marketplace.clone(), &QueryMsg::GetTakerFee {})
+            .unwrap();
+        assert_eq!(taker_fee.taker_fee, Uint64::new(5));
+    }
+
+    #[test]
+    fn instantiate_with_invalid_taker_address_fails() {
+        let mut app = App::default();
+        let marketplace_id = app.store_code(marketplace_contract());
+
+        let err: ContractError = app
+            .instantiate_contract(
+                marketplace_id,
+                Addr::unchecked(ADMIN),
+                &InstantiateMsg {
+                    taker_fee: Uint64::new(2),
+                    native_denom: DENOM.to_string(),
+                    taker_address: "INVALID".to_string(),
+                    registration_bond: Uint128::zero(),
+                    pull_payments: false,
+                },
+                &[],
+                "marketplace",
+                None,
+            )
+            .unwrap_err()
+            .downcast()
+            .unwrap();
+        assert_eq!(
+            err,
+            ContractError::InvalidAddress {
+                address: "INVALID".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn register_collection_with_royalty_too_high_fails() {
+        let mut suite = Suite::new();
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::RegisterCollection {
+                contract_address: "collection2".to_string(),
+                royalty_percentage: Some(91),
+                royalty_payment_address: Some(ARTIST.to_string()),
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::RoyaltyTooHigh {
+                percentage: 91,
+                max: MAX_ROYALTY_PERCENTAGE
+            }
+        );
+    }
+
+    #[test]
+    fn register_collection_with_invalid_royalty_address_fails() {
+        let mut suite = Suite::new();
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::RegisterCollection {
+                contract_address: "collection2".to_string(),
+                royalty_percentage: Some(5),
+                royalty_payment_address: Some("INVALID".to_string()),
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::InvalidAddress {
+                address: "INVALID".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn registration_requires_an_allowed_cw721() {
+        let mut suite = Suite::new();
+
+        for contract_address in [SELLER.to_string(), suite.marketplace.to_string()] {
+            let err = suite.execute_err(
+                ADMIN,
+                ExecuteMsg::RegisterCollection {
+                    contract_address: contract_address.clone(),
+                    royalty_percentage: None,
+                    royalty_payment_address: None,
+                },
+                &[],
+            );
+            assert_eq!(
+                err,
+                ContractError::NotCw721Contract {
+                    address: contract_address
+                }
+            );
+        }
+
+        let collection = suite.instantiate_collection(ADMIN);
+        let register = ExecuteMsg::RegisterCollection {
+            contract_address: collection.to_string(),
+            royalty_percentage: None,
+            royalty_payment_address: None,
+        };
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateAllowedCodeIds {
+                    add: vec![suite.nft_code_id + 100],
+                    remove: vec![],
+                },
+                &[],
+            )
+            .unwrap();
+        let err = suite.execute_err(ADMIN, register.clone(), &[]);
+        assert_eq!(
+            err,
+            ContractError::CodeIdNotAllowed {
+                code_id: suite.nft_code_id
+            }
+        );
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateAllowedCodeIds {
+                    add: vec![suite.nft_code_id],
+                    remove: vec![suite.nft_code_id + 100],
+                },
+                &[],
+            )
+            .unwrap();
+        let allowed: AllowedCodeIdsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetAllowedCodeIds {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(allowed.code_ids, vec![suite.nft_code_id]);
+
+        suite.execute(ADMIN, register, &[]).unwrap();
+        let info: CollectionsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetCollection {
+                    contract_address: collection.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(info.collections[0].code_id, Some(suite.nft_code_id));
+        assert_eq!(info.collections[0].name, Some("Collection".to_string()));
+    }
+
+    #[test]
+    fn submissions_and_approvals_respect_the_code_id_allowlist() {
+        let mut suite = Suite::new();
+        let collection = suite.instantiate_collection("creator");
+        let allow = |code_id: u64| ExecuteMsg::UpdateAllowedCodeIds {
+            add: vec![code_id],
+            remove: vec![],
+        };
+
+        let err = suite.execute_err(SELLER, allow(suite.nft_code_id), &[]);
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::SubmitCollection {
+                contract_address: SELLER.to_string(),
+                royalty_percentage: None,
+                royalty_payment_address: None,
+            },
+            &coins(100, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::NotCw721Contract {
+                address: SELLER.to_string()
+            }
+        );
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::SubmitCollection {
+                    contract_address: collection.to_string(),
+                    royalty_percentage: None,
+                    royalty_payment_address: None,
+                },
+                &coins(100, DENOM),
+            )
+            .unwrap();
+
+        // a code ID dropped from the allowlist after submission blocks the approval
+        suite
+            .execute(ADMIN, allow(suite.nft_code_id + 100), &[])
+            .unwrap();
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::ApproveCollection {
+                contract_address: collection.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::CodeIdNotAllowed {
+                code_id: suite.nft_code_id
+            }
+        );
+
+        let pending: PendingCollectionsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetPendingCollections {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(pending.collections.len(), 1);
+        let registered: StdResult<CollectionsInfo> = suite.app.wrap().query_wasm_smart(
+            suite.marketplace.clone(),
+            &QueryMsg::GetCollection {
+                contract_address: collection.to_string(),
+            },
+        );
+        assert!(registered.is_err());
+    }
+
+    #[test]
+    fn update_collection_failures() {
+        let mut suite = Suite::new();
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::UpdateCollection {
+                contract_address: "collection2".to_string(),
+                royalty_percentage: None,
+                royalty_payment_address: None,
+                is_paused: Some(false),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::CollectionNotExist {});
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::UpdateCollection {
+                contract_address: suite.nft.to_string(),
+                royalty_percentage: Some(95),
+                royalty_payment_address: Some(ARTIST.to_string()),
+                is_paused: Some(false),
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::RoyaltyTooHigh {
+                percentage: 95,
+                max: MAX_ROYALTY_PERCENTAGE
+            }
+        );
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::UpdateCollection {
+                contract_address: suite.nft.to_string(),
+                royalty_percentage: Some(5),
+                royalty_payment_address: Some("INVALID".to_string()),
+                is_paused: Some(false),
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::InvalidAddress {
+                address: "INVALID".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn buy_with_invalid_payment_fails() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Payment(PaymentError::NoFunds {}));
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "2".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, ContractError::SaleDoesNotExist {});
+    }
+
+    #[test]
+    fn collection_offer_escrow_and_refund() {
+        let mut suite = Suite::new();
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::RemoveCollectionOffer {
+                contract_address: suite.nft.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::OfferDoesNotExist {});
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::CreateCollectionOffer {
+                contract_address: suite.nft.to_string(),
+                price: coin(1_000, DENOM),
+            },
+            &coins(500, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::InvalidFunds {
+                expected: coin(1_000, DENOM),
+                received: coin(500, DENOM),
+            }
+        );
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::CreateCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                    price: coin(1_000, DENOM),
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(999_000));
+        assert_eq!(
+            suite.balance(suite.marketplace.as_str()),
+            Uint128::new(1_000)
+        );
+
+        // replacing an offer refunds the previous escrow
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::CreateCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                    price: coin(600, DENOM),
+                },
+                &coins(600, DENOM),
+            )
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(999_400));
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::new(600));
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::RemoveCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::zero());
+    }
+
+    #[test]
+    fn accept_collection_offer_settles() {
+        let mut suite = Suite::new();
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::CreateCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                    price: coin(1_000, DENOM),
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+        suite.mint(SELLER, "1");
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::AcceptCollectionOffer {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                offeror: BUYER.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::NotApproved {});
+
+        suite.approve(SELLER, "1");
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::AcceptCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    offeror: BUYER.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(suite.balance(BUYER), Uint128::new(999_000));
+        assert_eq!(suite.balance(TAKER), Uint128::new(20));
+        assert_eq!(suite.balance(ARTIST), Uint128::new(100));
+        assert_eq!(suite.balance(SELLER), Uint128::new(880));
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::zero());
+        assert_eq!(suite.nft_owner("1"), BUYER);
+
+        let offers: OffersInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetCollectionOffers {
+                    contract_address: suite.nft.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert!(offers.offers.is_empty());
+    }
+
+    #[test]
+    fn marketplace_pause_halts_trading() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::CreateCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                    price: coin(500, DENOM),
+                },
+                &coins(500, DENOM),
+            )
+            .unwrap();
+        suite.mint(SELLER, "2");
+        suite.approve(SELLER, "2");
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::PauseMarketplace {
+                expires_at_height: None,
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::PauseMarketplace {
+                    expires_at_height: None,
+                },
+                &[],
+            )
+            .unwrap();
+        assert!(suite.query_config().is_paused);
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, ContractError::MarketplacePaused {});
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::UpdateSale {
+                contract_address: suite.nft.to_string(),
+                token_id: "2".to_string(),
+                price: coin(1_000, DENOM),
+                starts_at: None,
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::MarketplacePaused {});
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::AcceptCollectionOffer {
+                contract_address: suite.nft.to_string(),
+                token_id: "2".to_string(),
+                offeror: BUYER.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::MarketplacePaused {});
+
+        // users can still exit their positions
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::RemoveSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::RemoveCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+
+        suite
+            .execute(ADMIN, ExecuteMsg::UnpauseMarketplace {}, &[])
+            .unwrap();
+        assert!(!suite.query_config().is_paused);
+    }
+
+    #[test]
+    fn marketplace_pause_expires() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::GrantRole {
+                    role: Role::Pauser,
+                    address: "pauser".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let height = suite.app.block_info().height;
+
+        let err = suite.execute_err(
+            "pauser",
+            ExecuteMsg::PauseMarketplace {
+                expires_at_height: Some(height),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::InvalidPauseExpiry { height });
+
+        suite
+            .execute(
+                "pauser",
+                ExecuteMsg::PauseMarketplace {
+                    expires_at_height: Some(height + 10),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, ContractError::MarketplacePaused {});
+
+        suite.app.update_block(|block| block.height += 10);
+        assert!(!suite.query_config().is_paused);
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+        assert_eq!(suite.nft_owner("1"), BUYER);
+    }
+
+    #[test]
+    fn paused_collection_blocks_buying() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateCollectionPause {
+                    contract_address: suite.nft.to_string(),
+                    is_paused: true,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::CollectionPaused {
+                contract_address: suite.nft.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn blocked_token_cannot_be_relisted() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::BlockToken {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                reason: "stolen".to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::BlockToken {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    reason: "stolen".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert!(suite.query_sale("1").is_err());
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::UpdateSale {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                price: coin(1_000, DENOM),
+                starts_at: None,
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::TokenBlocked {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                reason: "stolen".to_string(),
+            }
+        );
+
+        let blocked: BlockedTokensInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetBlockedTokens {
+                    contract_address: suite.nft.to_string(),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(blocked.tokens.len(), 1);
+        assert_eq!(blocked.tokens[0].token_id, "1");
+        assert_eq!(blocked.tokens[0].entry.reason, "stolen");
+        assert_eq!(blocked.tokens[0].entry.blocked_by, Addr::unchecked(ADMIN));
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UnblockToken {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    price: coin(1_000, DENOM),
+                    starts_at: None,
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn blocked_address_cannot_trade() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::GrantRole {
+                    role: Role::Moderator,
+                    address: "moderator".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                "moderator",
+                ExecuteMsg::BlockAddress {
+                    address: BUYER.to_string(),
+                    reason: "sanctioned".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let blocked_error = ContractError::AddressBlocked {
+            address: BUYER.to_string(),
+            reason: "sanctioned".to_string(),
+        };
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, blocked_error);
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::CreateCollectionOffer {
+                contract_address: suite.nft.to_string(),
+                price: coin(1_000, DENOM),
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, blocked_error);
+
+        let blocked: BlockedAddressesInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetBlockedAddresses {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(blocked.addresses.len(), 1);
+        assert_eq!(blocked.addresses[0].address, Addr::unchecked(BUYER));
+
+        suite
+            .execute(
+                "moderator",
+                ExecuteMsg::UnblockAddress {
+                    address: BUYER.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let err = suite.execute_err(
+            "moderator",
+            ExecuteMsg::UnblockAddress {
+                address: BUYER.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::NotBlocked {});
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn submitted_collection_is_approved() {
+        let mut suite = Suite::new();
+        let collection = suite.instantiate_collection("creator");
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::SubmitCollection {
+                contract_address: collection.to_string(),
+                royalty_percentage: None,
+                royalty_payment_address: None,
+            },
+            &coins(50, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::InvalidFunds {
+                expected: coin(100, DENOM),
+                received: coin(50, DENOM),
+            }
+        );
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::SubmitCollection {
+                    contract_address: collection.to_string(),
+                    royalty_percentage: None,
+                    royalty_payment_address: None,
+                },
+                &coins(100, DENOM),
+            )
+            .unwrap();
+
+        let pending: PendingCollectionsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetPendingCollections {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(pending.collections.len(), 1);
+        assert_eq!(pending.collections[0].contract_address, collection);
+        assert_eq!(pending.collections[0].pending.bond, Some(coin(100, DENOM)));
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::ApproveCollection {
+                contract_address: collection.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::ApproveCollection {
+                    contract_address: collection.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(suite.balance(BUYER), Uint128::new(999_900));
+        assert_eq!(suite.balance(TAKER), Uint128::new(100));
+
+        let collections: CollectionsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetCollection {
+                    contract_address: collection.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(collections.collections[0].royalty_percentage, None);
+    }
+
+    #[test]
+    fn rejected_collection_refunds_bond() {
+        let mut suite = Suite::new();
+        let collection = suite.instantiate_collection(BUYER);
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::SubmitCollection {
+                    contract_address: collection.to_string(),
+                    royalty_percentage: Some(5),
+                    royalty_payment_address: Some(BUYER.to_string()),
+                },
+                &coins(100, DENOM),
+            )
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(999_900));
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::SubmitCollection {
+                contract_address: collection.to_string(),
+                royalty_percentage: None,
+                royalty_payment_address: None,
+            },
+            &coins(100, DENOM),
+        );
+        assert_eq!(err, ContractError::CollectionAlreadyPending {});
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::RejectCollection {
+                    contract_address: collection.to_string(),
+                    reason: "spam".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::ApproveCollection {
+                contract_address: collection.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::CollectionNotPending {});
+    }
+
+    #[test]
+    fn creator_submission_replaces_a_third_party_one() {
+        let mut suite = Suite::new();
+        let collection = suite.instantiate_collection("creator");
+        suite
+            .app
+            .send_tokens(
+                Addr::unchecked(BUYER),
+                Addr::unchecked("creator"),
+                &coins(100, DENOM),
+            )
+            .unwrap();
+
+        let submit = |royalty_percentage: Option<u64>| ExecuteMsg::SubmitCollection {
+            contract_address: collection.to_string(),
+            royalty_percentage,
+            royalty_payment_address: None,
+        };
+        suite
+            .execute(BUYER, submit(None), &coins(100, DENOM))
+            .unwrap();
+        suite
+            .execute("creator", submit(Some(5)), &coins(100, DENOM))
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(999_900));
+
+        // nobody else can take the creator's slot back
+        let err = suite.execute_err(BUYER, submit(None), &coins(100, DENOM));
+        assert_eq!(err, ContractError::CollectionAlreadyPending {});
+
+        let pending: PendingCollectionsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetPendingCollections {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            pending.collections[0].pending.submitter,
+            Addr::unchecked("creator")
+        );
+        assert_eq!(pending.collections[0].pending.royalty_percentage, Some(5));
+    }
+
+    #[test]
+    fn registering_a_submitted_collection_refunds_bond() {
+        let mut suite = Suite::new();
+        let collection = suite.instantiate_collection(BUYER);
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::SubmitCollection {
+                    contract_address: collection.to_string(),
+                    royalty_percentage: None,
+                    royalty_payment_address: None,
+                },
+                &coins(100, DENOM),
+            )
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::RegisterCollection {
+                    contract_address: collection.to_string(),
+                    royalty_percentage: None,
+                    royalty_payment_address: None,
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::zero());
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::ApproveCollection {
+                contract_address: collection.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::CollectionNotPending {});
+    }
+
+    #[test]
+    fn only_creator_can_submit_royalty() {
+        let mut suite = Suite::new();
+        let collection = suite.instantiate_collection("creator");
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::SubmitCollection {
+                contract_address: collection.to_string(),
+                royalty_percentage: Some(5),
+                royalty_payment_address: Some(BUYER.to_string()),
+            },
+            &coins(100, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::NotCollectionCreator {
+                address: BUYER.to_string()
+            }
+        );
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::SubmitCollection {
+                contract_address: suite.nft.to_string(),
+                royalty_percentage: None,
+                royalty_payment_address: None,
+            },
+            &coins(100, DENOM),
+        );
+        assert_eq!(err, ContractError::CollectionAlreadyRegistered {});
+    }
+
+    #[test]
+    fn creator_updates_royalty_with_timelocked_increase() {
+        let mut suite = Suite::new();
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::UpdateCollectionRoyalty {
+                contract_address: suite.nft.to_string(),
+                royalty_percentage: Some(5),
+                royalty_payment_address: None,
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::NotCollectionCreator {
+                address: SELLER.to_string()
+            }
+        );
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::UpdateCollectionRoyalty {
+                contract_address: suite.nft.to_string(),
+                royalty_percentage: Some(95),
+                royalty_payment_address: None,
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::RoyaltyTooHigh {
+                percentage: 95,
+                max: MAX_ROYALTY_PERCENTAGE,
+            }
+        );
+
+        // decreases apply at once and leave the recipient alone
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateCollectionRoyalty {
+                    contract_address: suite.nft.to_string(),
+                    royalty_percentage: Some(5),
+                    royalty_payment_address: None,
+                },
+                &[],
+            )
+            .unwrap();
+        let collection = suite.query_collection().collections[0].clone();
+        assert_eq!(collection.royalty_percentage, Some(5));
+        assert_eq!(
+            collection.royalty_payment_address,
+            Some(Addr::unchecked(ARTIST))
+        );
+
+        // increases wait out the timelock
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateCollectionRoyalty {
+                    contract_address: suite.nft.to_string(),
+                    royalty_percentage: Some(20),
+                    royalty_payment_address: None,
+                },
+                &[],
+            )
+            .unwrap();
+        let collection = suite.query_collection().collections[0].clone();
+        assert_eq!(collection.royalty_percentage, Some(5));
+        assert_eq!(collection.pending_royalty.unwrap().royalty_percentage, 20);
+
+        suite.list("1", 1_000);
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+        assert_eq!(suite.balance(ARTIST), Uint128::new(50));
+        assert_eq!(suite.balance(SELLER), Uint128::new(930));
+
+        suite
+            .app
+            .update_block(|block| block.time = block.time.plus_seconds(ROYALTY_INCREASE_DELAY));
+
+        let collection = suite.query_collection().collections[0].clone();
+        assert_eq!(collection.royalty_percentage, Some(20));
+        assert_eq!(collection.pending_royalty, None);
+
+        suite.list("2", 1_000);
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "2".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+        assert_eq!(suite.balance(ARTIST), Uint128::new(250));
+        assert_eq!(suite.balance(SELLER), Uint128::new(1_710));
+    }
+
+    #[test]
+    fn adding_a_royalty_recipient_waits_out_the_timelock() {
+        let mut suite = Suite::new();
+        let collection = suite.instantiate_collection(ADMIN);
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::RegisterCollection {
+                    contract_address: collection.to_string(),
+                    royalty_percentage: Some(10),
+                    royalty_payment_address: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // without a recipient the 10% is not charged, so adding one is an increase
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateCollectionRoyalty {
+                    contract_address: collection.to_string(),
+                    royalty_percentage: None,
+                    royalty_payment_address: Some(ARTIST.to_string()),
+                },
+                &[],
+            )
+            .unwrap();
+        let simulate = |suite: &Suite| -> SaleSimulationInfo {
+            suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    suite.marketplace.clone(),
+                    &QueryMsg::SimulateSale {
+                        contract_address: collection.to_string(),
+                        price: coin(1_000, DENOM),
+                    },
+                )
+                .unwrap()
+        };
+        let simulation = simulate(&suite);
+        assert_eq!(simulation.royalty, Uint128::zero());
+        assert_eq!(simulation.royalty_recipient, None);
+
+        suite
+            .app
+            .update_block(|block| block.time = block.time.plus_seconds(ROYALTY_INCREASE_DELAY));
+        let simulation = simulate(&suite);
+        assert_eq!(simulation.royalty, Uint128::new(100));
+        assert_eq!(simulation.royalty_recipient, Some(Addr::unchecked(ARTIST)));
+    }
+
+    #[test]
+    fn escrowed_listing_is_repriced_and_bought() {
+        let mut suite = Suite::new();
+        suite.list_escrowed("1", 1_000);
+
+        assert_eq!(suite.nft_owner("1"), suite.marketplace.to_string());
+        let sale = suite.query_sale("1").unwrap().sales[0].clone();
+        assert!(sale.is_escrowed);
+        assert_eq!(sale.owner_address, Addr::unchecked(SELLER));
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::RemoveSale {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // the seller reprices without holding the token or an approval
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    price: coin(2_000, DENOM),
+                    starts_at: None,
+                },
+                &[],
+            )
+            .unwrap();
+        let sale = suite.query_sale("1").unwrap().sales[0].clone();
+        assert!(sale.is_escrowed);
+        assert_eq!(sale.price, coin(2_000, DENOM));
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(2_000, DENOM),
+            )
+            .unwrap();
+
+        assert_eq!(suite.nft_owner("1"), BUYER);
+        assert_eq!(suite.balance(TAKER), Uint128::new(40));
+        assert_eq!(suite.balance(ARTIST), Uint128::new(200));
+        assert_eq!(suite.balance(SELLER), Uint128::new(1_760));
+        assert!(suite.query_sale("1").is_err());
+    }
+
+    #[test]
+    fn removing_escrowed_listing_returns_token() {
+        let mut suite = Suite::new();
+        suite.list_escrowed("1", 1_000);
+
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::RemoveSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(suite.nft_owner("1"), SELLER);
+        assert!(suite.query_sale("1").is_err());
+
+        // blocking an escrowed token also hands it back
+        suite.list_escrowed("2", 1_000);
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::BlockToken {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "2".to_string(),
+                    reason: "stolen".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        assert_eq!(suite.nft_owner("2"), SELLER);
+        assert!(suite.query_sale("2").is_err());
+    }
+
+    #[test]
+    fn signed_order_is_filled_once() {
+        let mut suite = Suite::new();
+        let signer = OrderSigner::new();
+        suite.mint(&signer.address, "1");
+        suite.approve(&signer.address, "1");
+
+        let order = signer.order(&suite, "1", 1);
+
+        // a signature does not carry over to different terms
+        let mut tampered = order.clone();
+        tampered.price = coin(1, DENOM);
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::FillSignedOrder {
+                order: tampered,
+                pubkey: signer.pubkey.clone(),
+                signature: signer.sign(&suite, &order),
+            },
+            &coins(1, DENOM),
+        );
+        assert_eq!(err, ContractError::InvalidSignature {});
+
+        // nor can another key sign for the seller
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::FillSignedOrder {
+                order: order.clone(),
+                pubkey: Binary::from(vec![2u8; 33]),
+                signature: signer.sign(&suite, &order),
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, ContractError::InvalidSignature {});
+
+        suite
+            .execute(
+                BUYER,
+                signer.fill_msg(&suite, order.clone()),
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        assert_eq!(suite.nft_owner("1"), BUYER);
+        assert_eq!(suite.balance(TAKER), Uint128::new(20));
+        assert_eq!(suite.balance(ARTIST), Uint128::new(100));
+        assert_eq!(suite.balance(&signer.address), Uint128::new(880));
+
+        let err = suite.execute_err(BUYER, signer.fill_msg(&suite, order), &coins(1_000, DENOM));
+        assert_eq!(err, ContractError::NonceAlreadyUsed { nonce: 1 });
+
+        let nonce: OrderNonceInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetOrderNonce {
+                    seller: signer.address.clone(),
+                    nonce: 1,
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            nonce,
+            OrderNonceInfo {
+                min_nonce: 0,
+                is_used: true,
+            }
+        );
+    }
+
+    #[test]
+    fn signed_orders_are_cancelled_and_expire() {
+        let mut suite = Suite::new();
+        let signer = OrderSigner::new();
+        suite.mint(&signer.address, "1");
+        suite.approve(&signer.address, "1");
+
+        suite
+            .execute(
+                &signer.address,
+                ExecuteMsg::CancelOrderNonces { nonces: vec![1] },
+                &[],
+            )
+            .unwrap();
+        let err = suite.execute_err(
+            BUYER,
+            signer.fill_msg(&suite, signer.order(&suite, "1", 1)),
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, ContractError::NonceAlreadyUsed { nonce: 1 });
+
+        suite
+            .execute(
+                &signer.address,
+                ExecuteMsg::IncreaseMinNonce { min_nonce: 5 },
+                &[],
+            )
+            .unwrap();
+        let err = suite.execute_err(
+            BUYER,
+            signer.fill_msg(&suite, signer.order(&suite, "1", 3)),
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(err, ContractError::NonceAlreadyUsed { nonce: 3 });
+
+        let err = suite.execute_err(
+            &signer.address,
+            ExecuteMsg::IncreaseMinNonce { min_nonce: 5 },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::InvalidMinNonce {
+                min_nonce: 5,
+                current: 5,
+            }
+        );
+
+        let mut order = signer.order(&suite, "1", 5);
+        order.expires_at = suite.app.block_info().time;
+        let err = suite.execute_err(BUYER, signer.fill_msg(&suite, order), &coins(1_000, DENOM));
+        assert_eq!(err, ContractError::OrderExpired {});
+    }
+
+    #[test]
+    fn pull_payments_credit_proceeds_for_withdrawal() {
+        let mut suite = Suite::new();
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::UpdatePullPayments {
+                pull_payments: true,
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdatePullPayments {
+                    pull_payments: true,
+                },
+                &[],
+            )
+            .unwrap();
+        suite.list("1", 1_000);
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        assert_eq!(suite.nft_owner("1"), BUYER);
+        assert_eq!(suite.balance(SELLER), Uint128::zero());
+        assert_eq!(
+            suite.balance(suite.marketplace.as_str()),
+            Uint128::new(1_000)
+        );
+
+        for (address, amount) in [(SELLER, 880), (ARTIST, 100), (TAKER, 20)] {
+            let balances: BalancesInfo = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    suite.marketplace.clone(),
+                    &QueryMsg::GetBalances {
+                        address: address.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(balances.balances, coins(amount, DENOM));
+        }
+
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::Withdraw {
+                    denom: DENOM.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(suite.balance(SELLER), Uint128::new(880));
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::new(120));
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::Withdraw {
+                denom: DENOM.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::NothingToWithdraw {});
+    }
+
+    #[test]
+    fn pull_payment_withdrawals_survive_a_pause_but_not_a_second_claim() {
+        let mut suite = Suite::new();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdatePullPayments {
+                    pull_payments: true,
+                },
+                &[],
+            )
+            .unwrap();
+        suite.list("1", 1_000);
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        // nothing was credited to the buyer, nor in any other denom
+        let withdraw = |denom: &str| ExecuteMsg::Withdraw {
+            denom: denom.to_string(),
+        };
+        let err = suite.execute_err(BUYER, withdraw(DENOM), &[]);
+        assert_eq!(err, ContractError::NothingToWithdraw {});
+        let err = suite.execute_err(ARTIST, withdraw("uother"), &[]);
+        assert_eq!(err, ContractError::NothingToWithdraw {});
+
+        // switching pull payments off or pausing trading keeps credited proceeds claimable
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdatePullPayments {
+                    pull_payments: false,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::PauseMarketplace {
+                    expires_at_height: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite.execute(ARTIST, withdraw(DENOM), &[]).unwrap();
+        assert_eq!(suite.balance(ARTIST), Uint128::new(100));
+
+        let err = suite.execute_err(ARTIST, withdraw(DENOM), &[]);
+        assert_eq!(err, ContractError::NothingToWithdraw {});
+        assert_eq!(suite.balance(ARTIST), Uint128::new(100));
+    }
+
+    #[test]
+    fn completed_transfer_records_trade() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        let last: LastTradeInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetLastTrade {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                },
+            )
+            .unwrap();
+        let trade = last.trade.unwrap();
+        assert_eq!(trade.seller, Addr::unchecked(SELLER));
+        assert_eq!(trade.buyer, Addr::unchecked(BUYER));
+        assert_eq!(trade.price, coin(1_000, DENOM));
+    }
+
+    #[test]
+    fn buy_fails_when_collection_does_not_transfer() {
+        let mut suite = Suite::new();
+        let code_id = suite.app.store_code(stuck_cw721_contract());
+        let collection = suite
+            .app
+            .instantiate_contract(
+                code_id,
+                Addr::unchecked(ADMIN),
+                &Empty {},
+                &[],
+                "stuck",
+                None,
+            )
+            .unwrap();
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::RegisterCollection {
+                    contract_address: collection.to_string(),
+                    royalty_percentage: None,
+                    royalty_payment_address: None,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: collection.to_string(),
+                    token_id: "1".to_string(),
+                    price: coin(1_000, DENOM),
+                    starts_at: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: collection.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::TransferNotCompleted {
+                contract_address: collection.to_string(),
+                token_id: "1".to_string(),
+            }
+        );
+
+        // the whole sale is rolled back
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+        assert_eq!(suite.balance(SELLER), Uint128::zero());
+    }
+
+    #[test]
+    fn events_decode_from_responses() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite.list("2", 1_000);
+
+        let res = suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::AdminRemoveSales {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "2".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            ListingCancelled::find(&res.events).unwrap().unwrap(),
+            ListingCancelled {
+                contract_address: suite.nft.clone(),
+                token_id: "2".to_string(),
+                seller: Addr::unchecked(SELLER),
+                cancelled_by: Addr::unchecked(ADMIN),
+            }
+        );
+
+        let res = suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+        let sold = ListingSold::find(&res.events).unwrap().unwrap();
+        assert_eq!(
+            sold,
+            ListingSold {
+                contract_address: suite.nft.clone(),
+                token_id: "1".to_string(),
+                seller: Addr::unchecked(SELLER),
+                buyer: Addr::unchecked(BUYER),
+                price: coin(1_000, DENOM),
+                taker_fee: Uint128::new(20),
+                royalty: Uint128::new(100),
+                royalty_recipient: Some(Addr::unchecked(ARTIST)),
+                source: SaleSource::Listing,
+                nonce: None,
+            }
+        );
+        assert_eq!(ListingSold::from_event(&sold.to_event()).unwrap(), sold);
+        assert!(ListingCancelled::from_event(&sold.to_event()).is_err());
+
+        let res = suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::BlockAddress {
+                    address: BUYER.to_string(),
+                    reason: "fraud".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            AddressBlocked::find(&res.events).unwrap().unwrap(),
+            AddressBlocked {
+                address: Addr::unchecked(BUYER),
+                reason: "fraud".to_string(),
+            }
+        );
+
+        let res = suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::BlockToken {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    reason: "stolen".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            TokenBlocked::find(&res.events).unwrap().unwrap(),
+            TokenBlocked {
+                contract_address: suite.nft.clone(),
+                token_id: "1".to_string(),
+                reason: "stolen".to_string(),
+            }
+        );
+
+        let res = suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::PauseMarketplace {
+                    expires_at_height: Some(suite.app.block_info().height + 10),
+                },
+                &[],
+            )
+            .unwrap();
+        let paused = MarketplacePaused::find(&res.events).unwrap().unwrap();
+        assert_eq!(paused.paused_by, Addr::unchecked(ADMIN));
+        assert_eq!(
+            paused.expires_at_height,
+            Some(suite.app.block_info().height + 10)
+        );
+    }
+
+    #[test]
+    fn helper_attaches_listing_price_and_simulates_split() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let marketplace = MarketplaceContract(suite.marketplace.clone());
+        let querier = suite.app.wrap();
+
+        let simulation = marketplace
+            .simulate_sale(&querier, suite.nft.as_str(), coin(1_000, DENOM))
+            .unwrap();
+        assert_eq!(simulation.taker_fee, Uint128::new(20));
+        assert_eq!(simulation.royalty, Uint128::new(100));
+        assert_eq!(simulation.royalty_recipient, Some(Addr::unchecked(ARTIST)));
+        assert_eq!(simulation.seller_proceeds, Uint128::new(880));
+        assert_eq!(marketplace.taker_fee(&querier).unwrap(), Uint64::new(2));
+
+        let msg = marketplace.buy(&querier, suite.nft.as_str(), "1").unwrap();
+        suite.app.execute(Addr::unchecked(BUYER), msg).unwrap();
+
+        assert_eq!(suite.balance(SELLER), simulation.seller_proceeds);
+        assert_eq!(suite.balance(ARTIST), simulation.royalty);
+        assert_eq!(suite.nft_owner("1"), BUYER);
+    }
+
+    #[test]
+    fn helper_rejects_caller_priced_messages_and_stale_listings() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        let marketplace = MarketplaceContract(suite.marketplace.clone());
+
+        let msg = {
+            let querier = suite.app.wrap();
+
+            // the amount is the caller's to choose, so `execute` cannot attach it
+            assert!(marketplace
+                .execute(
+                    &querier,
+                    ExecuteMsg::ContributeToGroupBuy { group_buy_id: 1 }
+                )
+                .is_err());
+            assert!(marketplace
+                .execute(
+                    &querier,
+                    ExecuteMsg::CommitSealedBid {
+                        contract_address: suite.nft.to_string(),
+                        token_id: "1".to_string(),
+                        commitment: Binary::from(vec![0; 32]),
+                    },
+                )
+                .is_err());
+
+            // there is no listing to price a buy from
+            assert!(marketplace.buy(&querier, suite.nft.as_str(), "2").is_err());
+
+            marketplace.buy(&querier, suite.nft.as_str(), "1").unwrap()
+        };
+
+        // the built buy pins the listing it was priced from
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    price: coin(1_500, DENOM),
+                    starts_at: None,
+                },
+                &[],
+            )
+            .unwrap();
+        let err: ContractError = suite
+            .app
+            .execute(Addr::unchecked(BUYER), msg)
+            .unwrap_err()
+            .downcast()
+            .unwrap();
+        assert_eq!(
+            err,
+            ContractError::PriceChanged {
+                expected: coin(1_000, DENOM),
+                current: coin(1_500, DENOM),
+            }
+        );
+        assert_eq!(suite.nft_owner("1"), SELLER);
+    }
+
+    #[test]
+    fn curators_attach_searchable_collection_metadata() {
+        let mut suite = Suite::new();
+        let games = suite.instantiate_collection(ADMIN);
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::RegisterCollection {
+                    contract_address: games.to_string(),
+                    royalty_percentage: None,
+                    royalty_payment_address: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let metadata = |name: &str, category: &str, is_verified| CollectionMetadata {
+            name: name.to_string(),
+            description: None,
+            image_uri: Some(format!("ipfs://{name}")),
+            external_links: vec![],
+            category: Some(category.to_string()),
+            is_verified,
+        };
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::UpdateCollectionMetadata {
+                contract_address: suite.nft.to_string(),
+                metadata: metadata(" ", "art", true),
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::InvalidCollectionMetadata {
+                reason: "name is empty".to_string()
+            }
+        );
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::UpdateCollectionMetadata {
+                contract_address: "unregistered".to_string(),
+                metadata: metadata("Unregistered", "art", false),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::CollectionNotExist {});
+
+        for (contract_address, metadata) in [
+            (suite.nft.clone(), metadata("Art", "art", true)),
+            (games.clone(), metadata("Games", "gaming", false)),
+        ] {
+            suite
+                .execute(
+                    ADMIN,
+                    ExecuteMsg::UpdateCollectionMetadata {
+                        contract_address: contract_address.to_string(),
+                        metadata,
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        let info: CollectionMetadataInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetCollectionMetadata {
+                    contract_address: suite.nft.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(info.metadata, metadata("Art", "art", true));
+        assert_eq!(info.collection.royalty_percentage, Some(10));
+
+        let search = |suite: &Suite, category: Option<&str>, is_verified| -> Vec<Addr> {
+            let res: CollectionsMetadataInfo = suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    suite.marketplace.clone(),
+                    &QueryMsg::SearchCollections {
+                        category: category.map(str::to_string),
+                        is_verified,
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            res.collections
+                .into_iter()
+                .map(|info| info.contract_address)
+                .collect()
+        };
+        assert_eq!(search(&suite, None, None).len(), 2);
+        assert_eq!(search(&suite, Some("gaming"), None), vec![games]);
+        assert_eq!(search(&suite, None, Some(true)), vec![suite.nft.clone()]);
+        assert!(search(&suite, Some("gaming"), Some(true)).is_empty());
+    }
+
+    #[test]
+    fn collection_metadata_rejects_other_roles_and_oversized_fields() {
+        let mut suite = Suite::new();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::GrantRole {
+                    role: Role::Moderator,
+                    address: "moderator".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let valid = CollectionMetadata {
+            name: "Art".to_string(),
+            description: None,
+            image_uri: None,
+            external_links: vec![],
+            category: None,
+            is_verified: true,
+        };
+        let update = |metadata: CollectionMetadata| ExecuteMsg::UpdateCollectionMetadata {
+            contract_address: suite.nft.to_string(),
+            metadata,
+        };
+        let invalid = |reason: &str| ContractError::InvalidCollectionMetadata {
+            reason: reason.to_string(),
+        };
+        let cases = [
+            (
+                CollectionMetadata {
+                    name: "a".repeat(MAX_METADATA_NAME_LENGTH + 1),
+                    ..valid.clone()
+                },
+                invalid("name is too long"),
+            ),
+            (
+                CollectionMetadata {
+                    description: Some("a".repeat(MAX_METADATA_DESCRIPTION_LENGTH + 1)),
+                    ..valid.clone()
+                },
+                invalid("description is too long"),
+            ),
+            (
+                CollectionMetadata {
+                    external_links: vec!["https://example.com".to_string(); MAX_METADATA_LINKS + 1],
+                    ..valid.clone()
+                },
+                invalid("too many external links"),
+            ),
+        ]
+        .map(|(metadata, err)| (update(metadata), err));
+        let (seller_update, moderator_update) = (update(valid.clone()), update(valid));
+
+        // verification badges are for curators, not moderators or collection owners
+        for (sender, msg) in [(SELLER, seller_update), ("moderator", moderator_update)] {
+            let err = suite.execute_err(sender, msg, &[]);
+            assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+        }
+
+        for (msg, expected) in cases {
+            let err = suite.execute_err(ADMIN, msg, &[]);
+            assert_eq!(err, expected);
+        }
+
+        let res: StdResult<CollectionMetadataInfo> = suite.app.wrap().query_wasm_smart(
+            suite.marketplace.clone(),
+            &QueryMsg::GetCollectionMetadata {
+                contract_address: suite.nft.to_string(),
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn hooks_are_notified_of_listings_and_sales() {
+        let mut suite = Suite::new();
+        let recording_code_id = suite.app.store_code(recording_hook_contract());
+        let failing_code_id = suite.app.store_code(failing_hook_contract());
+        let mut instantiate_hook = |code_id| {
+            suite
+                .app
+                .instantiate_contract(
+                    code_id,
+                    Addr::unchecked(ADMIN),
+                    &Empty {},
+                    &[],
+                    "hook",
+                    None,
+                )
+                .unwrap()
+        };
+        let recorder = instantiate_hook(recording_code_id);
+        let failing = instantiate_hook(failing_code_id);
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::AddHook {
+                contract_address: recorder.to_string(),
+                ignore_errors: false,
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        for (hook, ignore_errors) in [(&recorder, false), (&failing, true)] {
+            suite
+                .execute(
+                    ADMIN,
+                    ExecuteMsg::AddHook {
+                        contract_address: hook.to_string(),
+                        ignore_errors,
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        suite.list("1", 1_000);
+        let res = suite
+            .execute(
+                BUYER,
+                ExecuteMsg::Buy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    expected_price: None,
+                    expected_version: None,
+                    merkle_proof: None,
+                },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+        assert_eq!(suite.nft_owner("1"), BUYER);
+        assert!(res
+            .events
+            .iter()
+            .any(|event| event.ty == "wasm-hook_failed"));
+
+        let received: Vec<MarketplaceHookMsg> = suite
+            .app
+            .wrap()
+            .query_wasm_smart(recorder.clone(), &Empty {})
+            .unwrap();
+        assert_eq!(
+            received,
+            vec![
+                MarketplaceHookMsg::Listed {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    seller: SELLER.to_string(),
+                    price: coin(1_000, DENOM),
+                },
+                MarketplaceHookMsg::Sold {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    seller: SELLER.to_string(),
+                    buyer: BUYER.to_string(),
+                    price: coin(1_000, DENOM),
+                },
+            ]
+        );
+
+        // a hook that does not ignore errors reverts the action
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::RemoveHook {
+                    contract_address: failing.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::AddHook {
+                    contract_address: failing.to_string(),
+                    ignore_errors: false,
+                },
+                &[],
+            )
+            .unwrap();
+
+        suite.mint(SELLER, "2");
+        suite.approve(SELLER, "2");
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "2".to_string(),
+                    price: coin(1_000, DENOM),
+                    starts_at: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert!(suite.query_sale("2").is_err());
+
+        let hooks: HooksInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetHooks {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(hooks.hooks.len(), 2);
+    }
+
+    #[test]
+    fn hooks_are_notified_of_listings_removed_by_other_sales() {
+        let mut suite = Suite::new();
+        let recording_code_id = suite.app.store_code(recording_hook_contract());
+        let recorder = suite
+            .app
+            .instantiate_contract(
+                recording_code_id,
+                Addr::unchecked(ADMIN),
+                &Empty {},
+                &[],
+                "hook",
+                None,
+            )
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::AddHook {
+                    contract_address: recorder.to_string(),
+                    ignore_errors: false,
+                },
+                &[],
+            )
+            .unwrap();
+
+        suite.list("1", 1_000);
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::CreateCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                    price: coin(500, DENOM),
+                },
+                &coins(500, DENOM),
+            )
+            .unwrap();
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::AcceptCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    offeror: BUYER.to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        assert!(suite.query_sale("1").is_err());
+
+        let received: Vec<MarketplaceHookMsg> = suite
+            .app
+            .wrap()
+            .query_wasm_smart(recorder, &Empty {})
+            .unwrap();
+        assert_eq!(
+            received,
+            vec![
+                MarketplaceHookMsg::Listed {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    seller: SELLER.to_string(),
+                    price: coin(1_000, DENOM),
+                },
+                MarketplaceHookMsg::Delisted {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    seller: SELLER.to_string(),
+                },
+                MarketplaceHookMsg::OfferAccepted {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    seller: SELLER.to_string(),
+                    offeror: BUYER.to_string(),
+                    price: coin(500, DENOM),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn sealed_auction_settles_at_second_price_and_penalizes_unrevealed_bids() {
+        let mut suite = Suite::new();
+        let recording_code_id = suite.app.store_code(recording_hook_contract());
+        let recorder = suite
+            .app
+            .instantiate_contract(
+                recording_code_id,
+                Addr::unchecked(ADMIN),
+                &Empty {},
+                &[],
+                "hook",
+                None,
+            )
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::AddHook {
+                    contract_address: recorder.to_string(),
+                    ignore_errors: false,
+                },
+                &[],
+            )
+            .unwrap();
+        suite.mint(SELLER, "1");
+        for bidder in ["bidder", "lurker"] {
+            suite
+                .app
+                .send_tokens(
+                    Addr::unchecked(BUYER),
+                    Addr::unchecked(bidder),
+                    &coins(1_000, DENOM),
+                )
+                .unwrap();
+        }
+
+        let now = suite.app.block_info().time;
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(SELLER),
+                suite.nft.clone(),
+                &cw721_rewards::ExecuteMsg::<Empty>::SendNft {
+                    contract: suite.marketplace.to_string(),
+                    token_id: "1".to_string(),
+                    msg: to_binary(&ReceiveNftMsg::StartSealedAuction {
+                        terms: SealedAuctionTerms {
+                            reserve_price: coin(300, DENOM),
+                            pricing: AuctionPricing::SecondPrice,
+                            starts_at: None,
+                            commit_ends_at: now.plus_seconds(100),
+                            reveal_ends_at: now.plus_seconds(200),
+                            unrevealed_penalty_percentage: 10,
+                        },
+                    })
+                    .unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let commitment = |suite: &Suite, bidder: &str, amount: u128| -> Binary {
+            let commitment = SealedBidCommitment {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                bidder: bidder.to_string(),
+                amount: Uint128::new(amount),
+                salt: "salt".to_string(),
+            };
+            Binary::from(Sha256::digest(to_vec(&commitment).unwrap()).to_vec())
+        };
+        let nft = suite.nft.to_string();
+        let reveal = |amount: u128| ExecuteMsg::RevealSealedBid {
+            contract_address: nft.clone(),
+            token_id: "1".to_string(),
+            amount: Uint128::new(amount),
+            salt: "salt".to_string(),
+        };
+
+        // deposits may exceed the bid they hide
+        for (bidder, amount, deposit) in [
+            (BUYER, 500, 600),
+            ("bidder", 800, 800),
+            ("lurker", 400, 400),
+        ] {
+            let commitment = commitment(&suite, bidder, amount);
+            suite
+                .execute(
+                    bidder,
+                    ExecuteMsg::CommitSealedBid {
+                        contract_address: suite.nft.to_string(),
+                        token_id: "1".to_string(),
+                        commitment,
+                    },
+                    &coins(deposit, DENOM),
+                )
+                .unwrap();
+        }
+
+        let err = suite.execute_err(BUYER, reveal(500), &[]);
+        assert_eq!(err, ContractError::RevealPhaseNotOpen {});
+
+        suite
+            .app
+            .update_block(|block| block.time = block.time.plus_seconds(100));
+        let err = suite.execute_err(BUYER, reveal(550), &[]);
+        assert_eq!(err, ContractError::CommitmentMismatch {});
+        suite.execute(BUYER, reveal(500), &[]).unwrap();
+        suite.execute("bidder", reveal(800), &[]).unwrap();
+
+        let settle = ExecuteMsg::SettleSealedAuction {
+            contract_address: suite.nft.to_string(),
+            token_id: "1".to_string(),
+        };
+        let err = suite.execute_err(ADMIN, settle.clone(), &[]);
+        assert_eq!(
+            err,
+            ContractError::AuctionNotEnded {
+                reveal_ends_at: now.plus_seconds(200),
+            }
+        );
+
+        suite
+            .app
+            .update_block(|block| block.time = block.time.plus_seconds(100));
+        suite.execute(ADMIN, settle, &[]).unwrap();
+
+        // the winner pays the second highest bid of 500, the lurker forfeits 10% of 400
+        assert_eq!(suite.nft_owner("1"), "bidder");
+        assert_eq!(suite.balance("bidder"), Uint128::new(500));
+        assert_eq!(suite.balance(BUYER), Uint128::new(998_000));
+        assert_eq!(suite.balance("lurker"), Uint128::new(960));
+        assert_eq!(suite.balance(TAKER), Uint128::new(10));
+        assert_eq!(suite.balance(ARTIST), Uint128::new(50));
+        assert_eq!(suite.balance(SELLER), Uint128::new(480));
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::zero());
+
+        let received: Vec<MarketplaceHookMsg> = suite
+            .app
+            .wrap()
+            .query_wasm_smart(recorder, &Empty {})
+            .unwrap();
+        assert_eq!(
+            received,
+            vec![MarketplaceHookMsg::Sold {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                seller: SELLER.to_string(),
+                buyer: "bidder".to_string(),
+                price: coin(500, DENOM),
+            }]
+        );
+    }
+
+    #[test]
+    fn sealed_auction_waits_out_a_pause_and_unwinds_for_blocked_parties() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+
+        let now = suite.app.block_info().time;
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(SELLER),
+                suite.nft.clone(),
+                &cw721_rewards::ExecuteMsg::<Empty>::SendNft {
+                    contract: suite.marketplace.to_string(),
+                    token_id: "1".to_string(),
+                    msg: to_binary(&ReceiveNftMsg::StartSealedAuction {
+                        terms: SealedAuctionTerms {
+                            reserve_price: coin(300, DENOM),
+                            pricing: AuctionPricing::FirstPrice,
+                            starts_at: None,
+                            commit_ends_at: now.plus_seconds(100),
+                            reveal_ends_at: now.plus_seconds(200),
+                            unrevealed_penalty_percentage: 10,
+                        },
+                    })
+                    .unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let commitment = SealedBidCommitment {
+            contract_address: suite.nft.to_string(),
+            token_id: "1".to_string(),
+            bidder: BUYER.to_string(),
+            amount: Uint128::new(500),
+            salt: "salt".to_string(),
+        };
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::CommitSealedBid {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    commitment: Binary::from(Sha256::digest(to_vec(&commitment).unwrap()).to_vec()),
+                },
+                &coins(600, DENOM),
+            )
+            .unwrap();
+
+        suite
+            .app
+            .update_block(|block| block.time = block.time.plus_seconds(100));
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::RevealSealedBid {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    amount: Uint128::new(500),
+                    salt: "salt".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateCollectionPause {
+                    contract_address: suite.nft.to_string(),
+                    is_paused: true,
+                },
+                &[],
+            )
+            .unwrap();
+
+        // a pause holds the auction until trading resumes
+        suite
+            .app
+            .update_block(|block| block.time = block.time.plus_seconds(100));
+        let settle = ExecuteMsg::SettleSealedAuction {
+            contract_address: suite.nft.to_string(),
+            token_id: "1".to_string(),
+        };
+        let err = suite.execute_err(SELLER, settle.clone(), &[]);
+        assert_eq!(
+            err,
+            ContractError::CollectionPaused {
+                contract_address: suite.nft.to_string(),
+            }
+        );
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateCollectionPause {
+                    contract_address: suite.nft.to_string(),
+                    is_paused: false,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::BlockAddress {
+                    address: BUYER.to_string(),
+                    reason: "fraud".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        // the blocked winner cannot buy, so every deposit and the token go back
+        suite.execute(SELLER, settle, &[]).unwrap();
+        assert_eq!(suite.nft_owner("1"), SELLER);
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+        assert_eq!(suite.balance(SELLER), Uint128::zero());
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::zero());
+    }
+
+    #[test]
+    fn blocking_a_token_unwinds_its_auction_and_releases_group_buys() {
+        let mut suite = Suite::new();
+        suite.mint(SELLER, "1");
+        suite.list("2", 1_000);
+
+        let now = suite.app.block_info().time;
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(SELLER),
+                suite.nft.clone(),
+                &cw721_rewards::ExecuteMsg::<Empty>::SendNft {
+                    contract: suite.marketplace.to_string(),
+                    token_id: "1".to_string(),
+                    msg: to_binary(&ReceiveNftMsg::StartSealedAuction {
+                        terms: SealedAuctionTerms {
+                            reserve_price: coin(300, DENOM),
+                            pricing: AuctionPricing::FirstPrice,
+                            starts_at: None,
+                            commit_ends_at: now.plus_seconds(100),
+                            reveal_ends_at: now.plus_seconds(200),
+                            unrevealed_penalty_percentage: 10,
+                        },
+                    })
+                    .unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::CommitSealedBid {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    commitment: Binary::from(Sha256::digest("bid").to_vec()),
+                },
+                &coins(600, DENOM),
+            )
+            .unwrap();
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::CreateGroupBuy {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "2".to_string(),
+                    recipient: "dao".to_string(),
+                    deadline: now.plus_seconds(3_600),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::ContributeToGroupBuy { group_buy_id: 0 },
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        for token_id in ["1", "2"] {
+            suite
+                .execute(
+                    ADMIN,
+                    ExecuteMsg::BlockToken {
+                        contract_address: suite.nft.to_string(),
+                        token_id: token_id.to_string(),
+                        reason: "stolen".to_string(),
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        // the auction's deposit and token go back at once
+        assert_eq!(suite.nft_owner("1"), SELLER);
+        assert_eq!(suite.balance(BUYER), Uint128::new(999_000));
+
+        // the group buy lost its listing, so it cannot settle and its funds can leave
+        let err = suite.execute_err(ADMIN, ExecuteMsg::SettleGroupBuy { group_buy_id: 0 }, &[]);
+        assert_eq!(err, ContractError::SaleDoesNotExist {});
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::WithdrawGroupBuyContribution { group_buy_id: 0 },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::zero());
+    }
+
+    #[test]
+    fn group_buy_pools_funds_for_a_listing() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite.list("2", 1_000);
+        suite
+            .app
+            .send_tokens(
+                Addr::unchecked(BUYER),
+                Addr::unchecked("friend"),
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        let deadline = suite.app.block_info().time.plus_seconds(3_600);
+        for token_id in ["1", "2"] {
+            suite
+                .execute(
+                    BUYER,
+                    ExecuteMsg::CreateGroupBuy {
+                        contract_address: suite.nft.to_string(),
+                        token_id: token_id.to_string(),
+                        recipient: "dao".to_string(),
+                        deadline,
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        let contribute = |group_buy_id| ExecuteMsg::ContributeToGroupBuy { group_buy_id };
+        suite
+            .execute(BUYER, contribute(0), &coins(600, DENOM))
+            .unwrap();
+        let err = suite.execute_err(BUYER, ExecuteMsg::SettleGroupBuy { group_buy_id: 0 }, &[]);
+        assert_eq!(
+            err,
+            ContractError::GroupBuyNotFunded {
+                remaining: Uint128::new(400),
+            }
+        );
+
+        // only the 400 still needed is kept
+        suite
+            .execute("friend", contribute(0), &coins(600, DENOM))
+            .unwrap();
+        assert_eq!(suite.balance("friend"), Uint128::new(600));
+
+        suite
+            .execute(ADMIN, ExecuteMsg::SettleGroupBuy { group_buy_id: 0 }, &[])
+            .unwrap();
+        assert_eq!(suite.nft_owner("1"), "dao");
+        assert_eq!(suite.balance(SELLER), Uint128::new(880));
+
+        // contributions to the second pool are locked until its listing changes
+        suite
+            .execute(BUYER, contribute(1), &coins(300, DENOM))
+            .unwrap();
+        let withdraw = ExecuteMsg::WithdrawGroupBuyContribution { group_buy_id: 1 };
+        let err = suite.execute_err(BUYER, withdraw.clone(), &[]);
+        assert_eq!(err, ContractError::GroupBuyStillOpen {});
+
+        suite
+            .execute(
+                SELLER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "2".to_string(),
+                    price: coin(1_500, DENOM),
+                    starts_at: None,
+                },
+                &[],
+            )
+            .unwrap();
+        let err = suite.execute_err("friend", contribute(1), &coins(100, DENOM));
+        assert_eq!(err, ContractError::GroupBuyClosed {});
+
+        suite.execute(BUYER, withdraw, &[]).unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(998_400));
+        assert_eq!(suite.balance(suite.marketplace.as_str()), Uint128::zero());
+    }
+
+    #[test]
+    fn group_buy_skips_private_sales_and_expires_at_its_deadline() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite.list("2", 1_000);
+        suite.list("3", 1_000);
+
+        let private_sale = |token_id: &str| ExecuteMsg::UpdateMerkleAllowlist {
+            contract_address: suite.nft.to_string(),
+            target: MerkleAllowlistTarget::Sale {
+                token_id: token_id.to_string(),
+            },
+            allowlist: Some(MerkleAllowlist {
+                root: Binary::from(Sha256::digest("friend").to_vec()),
+                with_allocations: false,
+            }),
+        };
+        let private_sale_1 = private_sale("1");
+        let private_sale_3 = private_sale("3");
+        suite.execute(SELLER, private_sale_1, &[]).unwrap();
+
+        let deadline = suite.app.block_info().time.plus_seconds(100);
+        let create = |token_id: &str| ExecuteMsg::CreateGroupBuy {
+            contract_address: suite.nft.to_string(),
+            token_id: token_id.to_string(),
+            recipient: "dao".to_string(),
+            deadline,
+        };
+        let (create_1, create_2, create_3) = (create("1"), create("2"), create("3"));
+        let err = suite.execute_err(BUYER, create_1, &[]);
+        assert_eq!(err, ContractError::GroupBuyOnPrivateSale {});
+
+        suite.execute(BUYER, create_2, &[]).unwrap();
+        suite.execute(BUYER, create_3, &[]).unwrap();
+        for group_buy_id in [0, 1] {
+            suite
+                .execute(
+                    BUYER,
+                    ExecuteMsg::ContributeToGroupBuy { group_buy_id },
+                    &coins(1_000, DENOM),
+                )
+                .unwrap();
+        }
+
+        // turning the listing private releases the pool funded for it
+        suite.execute(SELLER, private_sale_3, &[]).unwrap();
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::WithdrawGroupBuyContribution { group_buy_id: 1 },
+                &[],
+            )
+            .unwrap();
+
+        suite
+            .app
+            .update_block(|block| block.time = block.time.plus_seconds(100));
+        let err = suite.execute_err(ADMIN, ExecuteMsg::SettleGroupBuy { group_buy_id: 0 }, &[]);
+        assert_eq!(err, ContractError::GroupBuyExpired { deadline });
+
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::WithdrawGroupBuyContribution { group_buy_id: 0 },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+        assert_eq!(suite.nft_owner("2"), SELLER);
+    }
+
+    #[test]
+    fn launchpad_mints_through_phases() {
+        let mut suite = Suite::new();
+        suite
+            .app
+            .send_tokens(
+                Addr::unchecked(BUYER),
+                Addr::unchecked("friend"),
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        // the creator proposes the minter role to the marketplace, which accepts it
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                suite.nft.clone(),
+                &cw721_rewards::ExecuteMsg::<Empty>::UpdateOwnership(
+                    cw_ownable::Action::TransferOwnership {
+                        new_owner: suite.marketplace.to_string(),
+                        expiry: None,
+                    },
+                ),
+                &[],
+            )
+            .unwrap();
+
+        let now = suite.app.block_info().time;
+        let phase = |name: &str, price: u128, starts_at: u64, is_allowlisted: bool| MintPhase {
+            name: name.to_string(),
+            price: coin(price, DENOM),
+            starts_at: now.plus_seconds(starts_at),
+            ends_at: now.plus_seconds(starts_at + 100),
+            per_wallet_limit: Some(1),
+            max_supply: None,
+            is_allowlisted,
+            merkle_allowlist: None,
+        };
+        let terms = LaunchpadTerms {
+            payment_address: None,
+            phases: vec![
+                phase("allowlist", 100, 0, true),
+                phase("public", 200, 100, false),
+            ],
+            max_supply: 2,
+            starting_token_id: 1,
+            base_token_uri: Some("ipfs://launch".to_string()),
+        };
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::CreateLaunchpad {
+                contract_address: suite.nft.to_string(),
+                terms: terms.clone(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::NotCollectionMinter {});
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::CreateLaunchpad {
+                contract_address: suite.nft.to_string(),
+                terms: LaunchpadTerms {
+                    max_supply: 0,
+                    ..terms.clone()
+                },
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::InvalidMaxSupply {});
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::CreateLaunchpad {
+                    contract_address: suite.nft.to_string(),
+                    terms,
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateLaunchpadAllowlist {
+                    contract_address: suite.nft.to_string(),
+                    phase_id: 0,
+                    add: vec![BUYER.to_string()],
+                    remove: vec![],
+                },
+                &[],
+            )
+            .unwrap();
+
+        let mint = ExecuteMsg::Mint {
+            contract_address: suite.nft.to_string(),
+            merkle_proof: None,
+        };
+        let err = suite.execute_err("friend", mint.clone(), &coins(100, DENOM));
+        assert_eq!(err, ContractError::NotAllowlisted {});
+
+        suite
+            .execute(BUYER, mint.clone(), &coins(100, DENOM))
+            .unwrap();
+        let err = suite.execute_err(BUYER, mint.clone(), &coins(100, DENOM));
+        assert_eq!(err, ContractError::MintLimitReached { limit: 1 });
+
+        suite
+            .app
+            .update_block(|block| block.time = block.time.plus_seconds(100));
+        suite
+            .execute("friend", mint.clone(), &coins(200, DENOM))
+            .unwrap();
+        let err = suite.execute_err(BUYER, mint, &coins(200, DENOM));
+        assert_eq!(err, ContractError::LaunchpadSoldOut {});
+
+        assert_eq!(suite.nft_owner("1"), BUYER);
+        assert_eq!(suite.nft_owner("2"), "friend");
+        assert_eq!(suite.balance(TAKER), Uint128::new(6));
+        assert_eq!(suite.balance(ADMIN), Uint128::new(294));
+
+        let mints: LaunchpadMintsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetLaunchpadMints {
+                    contract_address: suite.nft.to_string(),
+                    phase_id: 1,
+                    address: "friend".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            mints,
+            LaunchpadMintsInfo {
+                minted: 1,
+                is_allowlisted: false,
+            }
+        );
+
+        // the launchpad creator still manages the royalty once the marketplace is the minter
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::UpdateCollectionRoyalty {
+                    contract_address: suite.nft.to_string(),
+                    royalty_percentage: Some(5),
+                    royalty_payment_address: None,
+                },
+                &[],
+            )
+            .unwrap();
+        assert_eq!(
+            suite.query_collection().collections[0].royalty_percentage,
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn mint_phase_supply_caps_its_mints() {
+        let mut suite = Suite::new();
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(ADMIN),
+                suite.nft.clone(),
+                &cw721_rewards::ExecuteMsg::<Empty>::UpdateOwnership(
+                    cw_ownable::Action::TransferOwnership {
+                        new_owner: suite.marketplace.to_string(),
+                        expiry: None,
+                    },
+                ),
+                &[],
+            )
+            .unwrap();
+
+        let now = suite.app.block_info().time;
+        let create = |max_supply: u64| ExecuteMsg::CreateLaunchpad {
+            contract_address: suite.nft.to_string(),
+            terms: LaunchpadTerms {
+                payment_address: None,
+                phases: vec![MintPhase {
+                    name: "free".to_string(),
+                    price: coin(0, DENOM),
+                    starts_at: now,
+                    ends_at: now.plus_seconds(100),
+                    per_wallet_limit: None,
+                    max_supply: Some(max_supply),
+                    is_allowlisted: false,
+                    merkle_allowlist: None,
+                }],
+                max_supply: 10,
+                starting_token_id: 1,
+                base_token_uri: None,
+            },
+        };
+        let (empty_phase, capped_phase) = (create(0), create(1));
+
+        let err = suite.execute_err(ADMIN, empty_phase, &[]);
+        assert_eq!(
+            err,
+            ContractError::InvalidMintPhases {
+                reason: "phase free must allow at least one mint".to_string(),
+            }
+        );
+        suite.execute(ADMIN, capped_phase, &[]).unwrap();
+
+        let mint = ExecuteMsg::Mint {
+            contract_address: suite.nft.to_string(),
+            merkle_proof: None,
+        };
+        suite.execute(BUYER, mint.clone(), &[]).unwrap();
+        let err = suite.execute_err("friend", mint, &[]);
+        assert_eq!(err, ContractError::MintPhaseSoldOut { phase_id: 0 });
+        assert_eq!(suite.nft_owner("1"), BUYER);
+    }
+
+    #[test]
+    fn private_sale_requires_a_merkle_proof_within_allocation() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite.list("2", 1_000);
+        suite
+            .app
+            .send_tokens(
+                Addr::unchecked(BUYER),
+                Addr::unchecked("friend"),
+                &coins(1_000, DENOM),
+            )
+            .unwrap();
+
+        let buyer_leaf = Sha256::digest(format!("{}:1", BUYER)).to_vec();
+        let friend_leaf = Sha256::digest("friend:1").to_vec();
+        let (first, second) = if buyer_leaf <= friend_leaf {
+            (&buyer_leaf, &friend_leaf)
+        } else {
+            (&friend_leaf, &buyer_leaf)
+        };
+        let root = Sha256::new()
+            .chain_update(first)
+            .chain_update(second)
+            .finalize()
+            .to_vec();
+        let allowlist = MerkleAllowlist {
+            root: Binary::from(root.clone()),
+            with_allocations: true,
+        };
+
+        for token_id in ["1", "2"] {
+            suite
+                .execute(
+                    SELLER,
+                    ExecuteMsg::UpdateMerkleAllowlist {
+                        contract_address: suite.nft.to_string(),
+                        target: MerkleAllowlistTarget::Sale {
+                            token_id: token_id.to_string(),
+                        },
+                        allowlist: Some(allowlist.clone()),
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        let buy =
+            |suite: &Suite, token_id: &str, merkle_proof: Option<MerkleProof>| ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: token_id.to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof,
+            };
+        let buyer_proof = MerkleProof {
+            allocation: Some(1),
+            proof: vec![Binary::from(friend_leaf)],
+        };
+
+        let msg = buy(&suite, "1", None);
+        let err = suite.execute_err("friend", msg, &coins(1_000, DENOM));
+        assert_eq!(err, ContractError::NotAllowlisted {});
+
+        // claiming a larger allocation changes the leaf
+        let msg = buy(
+            &suite,
+            "1",
+            Some(MerkleProof {
+                allocation: Some(2),
+                proof: vec![Binary::from(buyer_leaf)],
+            }),
+        );
+        let err = suite.execute_err("friend", msg, &coins(1_000, DENOM));
+        assert_eq!(err, ContractError::InvalidMerkleProof {});
+
+        let msg = buy(&suite, "1", Some(buyer_proof.clone()));
+        suite.execute(BUYER, msg, &coins(1_000, DENOM)).unwrap();
+        assert_eq!(suite.nft_owner("1"), BUYER);
+
+        let msg = buy(&suite, "2", Some(buyer_proof));
+        let err = suite.execute_err(BUYER, msg, &coins(1_000, DENOM));
+        assert_eq!(err, ContractError::AllocationExhausted { allocation: 1 });
+
+        let claims: MerkleClaimsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetMerkleClaims {
+                    merkle_root: Binary::from(root),
+                    address: BUYER.to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(claims.claimed, 1);
+    }
+
+    #[test]
+    fn relisting_after_transfer_drops_previous_private_sale() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let set_allowlist = ExecuteMsg::UpdateMerkleAllowlist {
+            contract_address: suite.nft.to_string(),
+            target: MerkleAllowlistTarget::Sale {
+                token_id: "1".to_string(),
+            },
+            allowlist: Some(MerkleAllowlist {
+                root: Binary::from(Sha256::digest("friend").to_vec()),
+                with_allocations: false,
+            }),
+        };
+        let err = suite.execute_err(ADMIN, set_allowlist.clone(), &[]);
+        assert_eq!(err, ContractError::Unauthorized {});
+        suite.execute(SELLER, set_allowlist, &[]).unwrap();
+
+        // the seller moves the token away, leaving the private listing behind
+        suite
+            .app
+            .execute_contract(
+                Addr::unchecked(SELLER),
+                suite.nft.clone(),
+                &cw721_rewards::ExecuteMsg::<Empty>::TransferNft {
+                    recipient: BUYER.to_string(),
+                    token_id: "1".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        suite.approve(BUYER, "1");
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::UpdateSale {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                    price: coin(1_000, DENOM),
+                    starts_at: None,
+                },
+                &[],
+            )
+            .unwrap();
+
+        let sale = suite.query_sale("1").unwrap().sales.remove(0);
+        assert_eq!(sale.owner_address, Addr::unchecked(BUYER));
+        assert_eq!(sale.merkle_allowlist, None);
+    }
+
+    #[test]
+    fn deregistered_collection_is_cleaned_up_in_batches() {
+        let mut suite = Suite::new();
+        suite.list_escrowed("1", 1_000);
+        suite.list("2", 1_000);
+        suite
+            .execute(
+                BUYER,
+                ExecuteMsg::CreateCollectionOffer {
+                    contract_address: suite.nft.to_string(),
+                    price: coin(500, DENOM),
+                },
+                &coins(500, DENOM),
+            )
+            .unwrap();
+
+        let deregister = ExecuteMsg::DeregisterCollection {
+            contract_address: suite.nft.to_string(),
+        };
+        let err = suite.execute_err(SELLER, deregister.clone(), &[]);
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+        suite.execute(ADMIN, deregister, &[]).unwrap();
+
+        suite.mint(SELLER, "3");
+        suite.approve(SELLER, "3");
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::UpdateSale {
+                contract_address: suite.nft.to_string(),
+                token_id: "3".to_string(),
+                price: coin(1_000, DENOM),
+                starts_at: None,
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::CollectionDeregistered {
+                contract_address: suite.nft.to_string(),
+            }
+        );
+
+        let cleanup = ExecuteMsg::CleanupDeregisteredCollection {
+            contract_address: suite.nft.to_string(),
+            limit: Some(2),
+        };
+        let query_progress = |suite: &Suite| -> DeregistrationInfo {
+            suite
+                .app
+                .wrap()
+                .query_wasm_smart(
+                    suite.marketplace.clone(),
+                    &QueryMsg::GetDeregistration {
+                        contract_address: suite.nft.to_string(),
+                    },
+                )
+                .unwrap()
+        };
+
+        // the first batch only reaches the listings
+        suite.execute(BUYER, cleanup.clone(), &[]).unwrap();
+        let progress = query_progress(&suite);
+        assert_eq!(progress.deregistration.as_ref().unwrap().sales_removed, 2);
+        assert_eq!(progress.remaining_sales, 0);
+        assert_eq!(progress.remaining_offers, 1);
+        assert_eq!(suite.nft_owner("1"), SELLER);
+        assert!(suite.query_sale("2").is_err());
+
+        suite.execute(BUYER, cleanup.clone(), &[]).unwrap();
+        let progress = query_progress(&suite);
+        let deregistration = progress.deregistration.unwrap();
+        assert_eq!(deregistration.offers_refunded, 1);
+        assert!(deregistration.completed_at.is_some());
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+
+        // the collection is gone and can be registered again
+        let err = suite.execute_err(BUYER, cleanup, &[]);
+        assert_eq!(
+            err,
+            ContractError::CollectionNotDeregistered {
+                contract_address: suite.nft.to_string(),
+            }
+        );
+        assert!(suite
+            .app
+            .wrap()
+            .query_wasm_smart::<CollectionsInfo>(
+                suite.marketplace.clone(),
+                &QueryMsg::GetCollection {
+                    contract_address: suite.nft.to_string(),
+                },
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn deregistration_rejects_unknown_repeated_and_premature_requests() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let cleanup = ExecuteMsg::CleanupDeregisteredCollection {
+            contract_address: suite.nft.to_string(),
+            limit: None,
+        };
+        let err = suite.execute_err(BUYER, cleanup, &[]);
+        assert_eq!(
+            err,
+            ContractError::CollectionNotDeregistered {
+                contract_address: suite.nft.to_string(),
+            }
+        );
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::DeregisterCollection {
+                contract_address: "unregistered".to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::CollectionNotExist {});
+
+        let deregister = ExecuteMsg::DeregisterCollection {
+            contract_address: suite.nft.to_string(),
+        };
+        suite.execute(ADMIN, deregister.clone(), &[]).unwrap();
+        let err = suite.execute_err(ADMIN, deregister, &[]);
+        assert_eq!(
+            err,
+            ContractError::CollectionDeregistered {
+                contract_address: suite.nft.to_string(),
+            }
+        );
+
+        // listings awaiting cleanup can no longer be bought
+        let err = suite.execute_err(
+            BUYER,
+            ExecuteMsg::Buy {
+                contract_address: suite.nft.to_string(),
+                token_id: "1".to_string(),
+                expected_price: None,
+                expected_version: None,
+                merkle_proof: None,
+            },
+            &coins(1_000, DENOM),
+        );
+        assert_eq!(
+            err,
+            ContractError::CollectionDeregistered {
+                contract_address: suite.nft.to_string(),
+            }
+        );
+        assert_eq!(suite.nft_owner("1"), SELLER);
+        assert_eq!(suite.balance(BUYER), Uint128::new(1_000_000));
+    }
+
+    #[test]
+    fn query_sales_and_collections() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+        suite.list("2", 2_000);
+
+        let query_sales =
+            |suite: &Suite, start_after: Option<(String, String)>| -> ListedSalesInfo {
+                suite
+                    .app
+                    .wrap()
+                    .query_wasm_smart(
+                        suite.marketplace.clone(),
+                        &QueryMsg::GetSales {
+                            start_after,
+                            limit: Some(1),
+                            status: None,
+                        },
+                    )
+                    .unwrap()
+            };
+
+        // each page resumes after the last key of the previous one
+        let first = query_sales(&suite, None).sales;
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].contract_address, suite.nft);
+        assert_eq!(first[0].token_id, "1");
+        assert_eq!(first[0].sale.price, coin(1_000, DENOM));
+
+        let second = query_sales(&suite, Some((suite.nft.to_string(), "1".to_string()))).sales;
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].token_id, "2");
+        assert_eq!(second[0].sale.price, coin(2_000, DENOM));
+
+        let last = query_sales(&suite, Some((suite.nft.to_string(), "2".to_string()))).sales;
+        assert!(last.is_empty());
+
+        let collections: RegisteredCollectionsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetCollections {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(collections.collections.len(), 1);
+        assert_eq!(collections.collections[0].contract_address, suite.nft);
+        assert_eq!(
+            collections.collections[0].collection.royalty_percentage,
+            Some(10)
+        );
+
+        let collections: RegisteredCollectionsInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetCollections {
+                    start_after: Some(suite.nft.to_string()),
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert!(collections.collections.is_empty());
+    }
+
+    #[test]
+    fn roles_grant_admin_actions() {
+        let mut suite = Suite::new();
+        suite.list("1", 1_000);
+
+        let err = suite.execute_err(
+            SELLER,
+            ExecuteMsg::GrantRole {
+                role: Role::Moderator,
+                address: SELLER.to_string(),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        for role in [Role::Moderator, Role::FeeAdmin] {
+            suite
+                .execute(
+                    ADMIN,
+                    ExecuteMsg::GrantRole {
+                        role,
+                        address: "operator".to_string(),
+                    },
+                    &[],
+                )
+                .unwrap();
+        }
+
+        let roles: RolesInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetRoles {
+                    address: "operator".to_string(),
+                },
+            )
+            .unwrap();
+        assert_eq!(roles.roles, vec![Role::FeeAdmin, Role::Moderator]);
+
+        let members: RoleMembersInfo = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                suite.marketplace.clone(),
+                &QueryMsg::GetRoleMembers {
+                    role: Role::Moderator,
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(members.members, vec![Addr::unchecked("operator")]);
+
+        suite
+            .execute(
+                "operator",
+                ExecuteMsg::UpdateTakerFee {
+                    taker_fee: Uint64::new(3),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                "operator",
+                ExecuteMsg::UpdateCollectionPause {
+                    contract_address: suite.nft.to_string(),
+                    is_paused: true,
+                },
+                &[],
+            )
+            .unwrap();
+        assert!(suite.query_collection().collections[0].is_paused);
+        suite
+            .execute(
+                "operator",
+                ExecuteMsg::AdminRemoveSales {
+                    contract_address: suite.nft.to_string(),
+                    token_id: "1".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        // moderators cannot change royalties
+        let err = suite.execute_err(
+            "operator",
+            ExecuteMsg::UpdateCollection {
+                contract_address: suite.nft.to_string(),
+                royalty_percentage: Some(50),
+                royalty_payment_address: Some("operator".to_string()),
+                is_paused: Some(false),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+    }
+
+    #[test]
+    fn revoke_role() {
+        let mut suite = Suite::new();
+
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::GrantRole {
+                    role: Role::FeeAdmin,
+                    address: "operator".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+        suite
+            .execute(
+                ADMIN,
+                ExecuteMsg::RevokeRole {
+                    role: Role::FeeAdmin,
+                    address: "operator".to_string(),
+                },
+                &[],
+            )
+            .unwrap();
+
+        let err = suite.execute_err(
+            "operator",
+            ExecuteMsg::UpdateTakerFee {
+                taker_fee: Uint64::new(3),
+            },
+            &[],
+        );
+        assert_eq!(err, ContractError::Ownership(OwnershipError::NotOwner));
+
+        let err = suite.execute_err(
+            ADMIN,
+            ExecuteMsg::RevokeRole {
+                role: Role::FeeAdmin,
+                address: "operator".to_string(),
+            },
+            &[],
+        );
+        assert_eq!(
+            err,
+            ContractError::RoleNotGranted {
+                role: "fee_admin".to_string(),
+                address: "operator".to_string(),
+            }
+        );
+    }
+
+    fn setup_v0_1_0(deps: DepsMut) {
+        Item::<u64>::new("taker_fee")
+            .save(deps.storage, &2)
+            .unwrap();
+        Item::<Addr>::new("taker_address")
+            .save(deps.storage, &Addr::unchecked("taker"))
+            .unwrap();
+        Item::<String>::new("native_denom")
+            .save(deps.storage, &"aconst".to_string())
+            .unwrap();
+        set_contract_version(deps.storage, CONTRACT_NAME, "0.1.0").unwrap();
+    }
+
+    #[test]
+    fn migrate_from_v0_1_0_moves_config() {
+        let mut deps = mock_dependencies();
+        setup_v0_1_0(deps.as_mut());
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg::Migrate {
+                taker_fee: None,
+                taker_address: None,
+                registration_bond: None,
+                pull_payments: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap(),
+            Config {
+                taker_fee: 2,
+                taker_address: Addr::unchecked("taker"),
+                native_denom: "aconst".to_string(),
+                registration_bond: Uint128::zero(),
+                pull_payments: false,
+            }
+        );
+        assert_eq!(
+            Item::<u64>::new("taker_fee")
+                .may_load(&deps.storage)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            get_contract_version(&deps.storage).unwrap().version,
+            CONTRACT_VERSION
+        );
+    }
+
+    #[test]
+    fn migrate_from_v0_1_0_reads_stored_listings_and_collections() {
+        let mut deps = mock_dependencies();
+        setup_v0_1_0(deps.as_mut());
+
+        // records as 0.1.0 wrote them, before any field added since
+        let nft = Addr::unchecked("nft");
+        deps.storage.set(
+            &SALES.key((nft.clone(), "1".to_string())),
+            br#"{"owner_address":"seller","price":{"denom":"aconst","amount":"1000"}}"#,
+        );
+        deps.storage.set(
+            &COLLECTIONS.key(nft.clone()),
+            br#"{"royalty_percentage":10,"royalty_payment_address":"artist","is_paused":false}"#,
+        );
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg::Migrate {
+                taker_fee: None,
+                taker_address: None,
+                registration_bond: None,
+                pull_payments: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            SALES
+                .load(&deps.storage, (nft.clone(), "1".to_string()))
+                .unwrap(),
+            Sale {
+                owner_address: Addr::unchecked("seller"),
+                price: coin(1_000, "aconst"),
+                is_escrowed: false,
+                version: 0,
+                starts_at: None,
+                merkle_allowlist: None,
+            }
+        );
+        assert_eq!(
+            COLLECTIONS.load(&deps.storage, nft).unwrap(),
+            Collection {
+                royalty_percentage: Some(10),
+                royalty_payment_address: Some(Addr::unchecked("artist")),
+                is_paused: false,
+                pending_royalty: None,
+                code_id: None,
+                name: None,
+                is_deregistered: false,
+            }
+        );
+    }
+
+    #[test]
+    fn migrate_applies_config_values() {
+        let mut deps = mock_dependencies();
+        setup_v0_1_0(deps.as_mut());
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg::Migrate {
+                taker_fee: Some(Uint64::new(5)),
+                taker_address: Some("new_taker".to_string()),
+                registration_bond: Some(Uint128::new(250)),
+                pull_payments: Some(true),
+            },
+        )
+        .unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.taker_fee, 5);
+        assert_eq!(config.taker_address, Addr::unchecked("new_taker"));
+        assert_eq!(config.registration_bond, Uint128::new(250));
+        assert!(config.pull_payments);
+    }
+
+    #[test]
+    fn migrate_refuses_downgrade() {
+        let mut deps = mock_dependencies();
+        setup_v0_1_0(deps.as_mut());
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+
+        let err = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg::Migrate {
+                taker_fee: None,
+                taker_address: None,
+                registration_bond: None,
+                pull_payments: None,
+            },
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ContractError::CannotMigrateDowngrade {
+                from: "99.0.0".to_string(),
+                to: CONTRACT_VERSION.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn migrate_refuses_foreign_contract() {
+        let mut deps = mock_dependencies();
+        setup_v0_1_0(deps.as_mut());
+        set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.1.0").unwrap();
+
+        let err = migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg::Migrate {
+                taker_fee: None,
+                taker_address: None,
+                registration_bond: None,
+                pull_payments: None,
+            },
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err,
+            ContractError::InvalidContractName {
+                expected: CONTRACT_NAME.to_string(),
+                actual: "crates.io:cw20-base".to_string(),
+            }
+        );
+    }
+}
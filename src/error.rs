@@ -1,8 +1,9 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Coin, OverflowError, StdError, Timestamp, Uint128};
 use cw_ownable::OwnershipError;
+use cw_utils::PaymentError;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
@@ -19,6 +20,24 @@ pub enum ContractError {
     #[error("CollectionNotExist")]
     CollectionNotExist {},
 
+    #[error("NotCw721Contract: {address}")]
+    NotCw721Contract { address: String },
+
+    #[error("CodeIdNotAllowed: {code_id}")]
+    CodeIdNotAllowed { code_id: u64 },
+
+    #[error("CollectionAlreadyPending")]
+    CollectionAlreadyPending {},
+
+    #[error("CollectionNotPending")]
+    CollectionNotPending {},
+
+    #[error("NotCollectionCreator: {address} is neither minter nor admin of the collection")]
+    NotCollectionCreator { address: String },
+
+    #[error("InvalidCollectionMetadata: {reason}")]
+    InvalidCollectionMetadata { reason: String },
+
     #[error("SaleDoesNotExist")]
     SaleDoesNotExist {},
 
@@ -28,6 +47,199 @@ pub enum ContractError {
     #[error("InsufficientFunds")]
     InsufficientFunds {},
 
+    #[error("InvalidAddress: {address}")]
+    InvalidAddress { address: String },
+
+    #[error("RoyaltyTooHigh: {percentage}% exceeds the maximum of {max}%")]
+    RoyaltyTooHigh { percentage: u64, max: u64 },
+
+    #[error("RoleNotGranted: {address} does not hold {role}")]
+    RoleNotGranted { role: String, address: String },
+
+    #[error("MarketplacePaused")]
+    MarketplacePaused {},
+
+    #[error("CollectionPaused: {contract_address}")]
+    CollectionPaused { contract_address: String },
+
+    #[error("CollectionDeregistered: {contract_address}")]
+    CollectionDeregistered { contract_address: String },
+
+    #[error("CollectionNotDeregistered: {contract_address} has no cleanup in progress")]
+    CollectionNotDeregistered { contract_address: String },
+
+    #[error("InvalidPauseExpiry: height {height} has already passed")]
+    InvalidPauseExpiry { height: u64 },
+
+    #[error("OfferDoesNotExist")]
+    OfferDoesNotExist {},
+
+    #[error("InvalidFunds: expected {expected}, received {received}")]
+    InvalidFunds { expected: Coin, received: Coin },
+
+    #[error("AddressBlocked: {address} ({reason})")]
+    AddressBlocked { address: String, reason: String },
+
+    #[error("TokenBlocked: {token_id} in {contract_address} ({reason})")]
+    TokenBlocked {
+        contract_address: String,
+        token_id: String,
+        reason: String,
+    },
+
+    #[error("NotBlocked")]
+    NotBlocked {},
+
+    #[error("PriceChanged: expected {expected}, listed at {current}")]
+    PriceChanged { expected: Coin, current: Coin },
+
+    #[error("ListingVersionChanged: expected {expected}, listing is at {current}")]
+    ListingVersionChanged { expected: u64, current: u64 },
+
+    #[error("ListingNotStarted: buyable from {starts_at}")]
+    ListingNotStarted { starts_at: Timestamp },
+
+    #[error("HookAlreadyRegistered: {contract_address}")]
+    HookAlreadyRegistered { contract_address: String },
+
+    #[error("HookNotRegistered: {contract_address}")]
+    HookNotRegistered { contract_address: String },
+
+    #[error("InvalidSignature")]
+    InvalidSignature {},
+
+    #[error("OrderExpired")]
+    OrderExpired {},
+
+    #[error("NonceAlreadyUsed: {nonce}")]
+    NonceAlreadyUsed { nonce: u64 },
+
+    #[error("InvalidMinNonce: {min_nonce} must be above {current}")]
+    InvalidMinNonce { min_nonce: u64, current: u64 },
+
+    #[error("NothingToWithdraw")]
+    NothingToWithdraw {},
+
+    #[error("TransferNotCompleted: {token_id} in {contract_address} did not reach the buyer")]
+    TransferNotCompleted {
+        contract_address: String,
+        token_id: String,
+    },
+
+    #[error("AuctionDoesNotExist")]
+    AuctionDoesNotExist {},
+
+    #[error("InvalidAuctionSchedule: {reason}")]
+    InvalidAuctionSchedule { reason: String },
+
+    #[error("InvalidPenaltyPercentage: {percentage} is above 100")]
+    InvalidPenaltyPercentage { percentage: u64 },
+
+    #[error("CommitPhaseNotOpen")]
+    CommitPhaseNotOpen {},
+
+    #[error("RevealPhaseNotOpen")]
+    RevealPhaseNotOpen {},
+
+    #[error("AuctionNotEnded: reveal phase ends at {reveal_ends_at}")]
+    AuctionNotEnded { reveal_ends_at: Timestamp },
+
+    #[error("AuctionHasBids")]
+    AuctionHasBids {},
+
+    #[error("TooManyBids: an auction accepts at most {max} bids")]
+    TooManyBids { max: u32 },
+
+    #[error("BidAlreadyCommitted")]
+    BidAlreadyCommitted {},
+
+    #[error("BidDoesNotExist")]
+    BidDoesNotExist {},
+
+    #[error("BidAlreadyRevealed")]
+    BidAlreadyRevealed {},
+
+    #[error("CommitmentMismatch")]
+    CommitmentMismatch {},
+
+    #[error("GroupBuyDoesNotExist")]
+    GroupBuyDoesNotExist {},
+
+    #[error("InvalidGroupBuyDeadline: {deadline} has passed")]
+    InvalidGroupBuyDeadline { deadline: Timestamp },
+
+    #[error("GroupBuyClosed")]
+    GroupBuyClosed {},
+
+    #[error("GroupBuyNotFunded: {remaining} remaining")]
+    GroupBuyNotFunded { remaining: Uint128 },
+
+    #[error("GroupBuyStillOpen")]
+    GroupBuyStillOpen {},
+
+    #[error("GroupBuyExpired: {deadline} has passed")]
+    GroupBuyExpired { deadline: Timestamp },
+
+    #[error("GroupBuyOnPrivateSale")]
+    GroupBuyOnPrivateSale {},
+
+    #[error("LaunchpadAlreadyExists")]
+    LaunchpadAlreadyExists {},
+
+    #[error("LaunchpadDoesNotExist")]
+    LaunchpadDoesNotExist {},
+
+    #[error("NotCollectionMinter")]
+    NotCollectionMinter {},
+
+    #[error("InvalidMintPhases: {reason}")]
+    InvalidMintPhases { reason: String },
+
+    #[error("InvalidMaxSupply")]
+    InvalidMaxSupply {},
+
+    #[error("MintPhaseDoesNotExist: {phase_id}")]
+    MintPhaseDoesNotExist { phase_id: u32 },
+
+    #[error("NoActiveMintPhase")]
+    NoActiveMintPhase {},
+
+    #[error("NotAllowlisted")]
+    NotAllowlisted {},
+
+    #[error("MintLimitReached: at most {limit} per wallet")]
+    MintLimitReached { limit: u32 },
+
+    #[error("LaunchpadSoldOut")]
+    LaunchpadSoldOut {},
+
+    #[error("MintPhaseSoldOut: {phase_id}")]
+    MintPhaseSoldOut { phase_id: u32 },
+
+    #[error("InvalidMerkleRoot: expected a 32 byte sha256 hash")]
+    InvalidMerkleRoot {},
+
+    #[error("InvalidMerkleProof")]
+    InvalidMerkleProof {},
+
+    #[error("AllocationExhausted: {allocation} already used")]
+    AllocationExhausted { allocation: u32 },
+
+    #[error("InvalidContractName: expected {expected}, got {actual}")]
+    InvalidContractName { expected: String, actual: String },
+
+    #[error("CannotMigrateDowngrade: from {from} to {to}")]
+    CannotMigrateDowngrade { from: String, to: String },
+
     #[error(transparent)]
     Ownership(#[from] OwnershipError),
+
+    #[error(transparent)]
+    Version(#[from] semver::Error),
+
+    #[error(transparent)]
+    Payment(#[from] PaymentError),
+
+    #[error(transparent)]
+    Overflow(#[from] OverflowError),
 }
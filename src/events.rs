@@ -0,0 +1,671 @@
+//! Typed marketplace events.
+//!
+//! Every event is emitted with a `schema_version` attribute and can be decoded back from the
+//! `cosmwasm_std::Event` an indexer receives, with or without the `wasm-` prefix the chain adds.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use cosmwasm_std::{Addr, Coin, Event, StdError, StdResult, Timestamp, Uint128};
+
+/// Bumped whenever an event's name or attributes change
+pub const EVENT_SCHEMA_VERSION: &str = "1";
+
+pub trait MarketplaceEvent: Sized {
+    const NAME: &'static str;
+
+    fn to_event(&self) -> Event;
+
+    fn from_event(event: &Event) -> StdResult<Self>;
+
+    /// Decodes the first event of this type in `events`, if there is one.
+    fn find(events: &[Event]) -> Option<StdResult<Self>> {
+        events
+            .iter()
+            .find(|event| event_name(event) == Self::NAME)
+            .map(Self::from_event)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListingCreated {
+    pub contract_address: Addr,
+    pub token_id: String,
+    pub seller: Addr,
+    pub price: Coin,
+    pub is_escrowed: bool,
+    pub version: u64,
+    pub starts_at: Option<Timestamp>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListingUpdated {
+    pub contract_address: Addr,
+    pub token_id: String,
+    pub seller: Addr,
+    pub price: Coin,
+    pub is_escrowed: bool,
+    pub version: u64,
+    pub starts_at: Option<Timestamp>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListingCancelled {
+    pub contract_address: Addr,
+    pub token_id: String,
+    pub seller: Addr,
+    /// The seller, or the moderator who removed the listing
+    pub cancelled_by: Addr,
+}
+
+/// How a sold token was offered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SaleSource {
+    Listing,
+    SignedOrder,
+    CollectionOffer,
+    SealedAuction,
+}
+
+impl SaleSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SaleSource::Listing => "listing",
+            SaleSource::SignedOrder => "signed_order",
+            SaleSource::CollectionOffer => "collection_offer",
+            SaleSource::SealedAuction => "sealed_auction",
+        }
+    }
+}
+
+impl FromStr for SaleSource {
+    type Err = StdError;
+
+    fn from_str(source: &str) -> StdResult<Self> {
+        match source {
+            "listing" => Ok(SaleSource::Listing),
+            "signed_order" => Ok(SaleSource::SignedOrder),
+            "collection_offer" => Ok(SaleSource::CollectionOffer),
+            "sealed_auction" => Ok(SaleSource::SealedAuction),
+            _ => Err(StdError::parse_err("SaleSource", source)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListingSold {
+    pub contract_address: Addr,
+    pub token_id: String,
+    pub seller: Addr,
+    pub buyer: Addr,
+    pub price: Coin,
+    pub taker_fee: Uint128,
+    pub royalty: Uint128,
+    pub royalty_recipient: Option<Addr>,
+    pub source: SaleSource,
+    /// Signed order nonce, for `SaleSource::SignedOrder`
+    pub nonce: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OfferCreated {
+    pub contract_address: Addr,
+    pub offeror: Addr,
+    pub price: Coin,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OfferCancelled {
+    pub contract_address: Addr,
+    pub offeror: Addr,
+    /// Escrow returned to the offeror
+    pub price: Coin,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OfferAccepted {
+    pub contract_address: Addr,
+    pub token_id: String,
+    pub offeror: Addr,
+    pub seller: Addr,
+    pub price: Coin,
+}
+
+/// A config change, listing only the values that changed
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigUpdated {
+    pub updated_by: Addr,
+    pub taker_fee: Option<u64>,
+    pub registration_bond: Option<Uint128>,
+    pub pull_payments: Option<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnershipUpdated {
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
+    pub pending_expiry: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollectionRegistered {
+    pub contract_address: Addr,
+    pub royalty_percentage: Option<u64>,
+    pub royalty_payment_address: Option<Addr>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollectionPauseUpdated {
+    pub contract_address: Addr,
+    pub is_paused: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketplacePaused {
+    pub paused_by: Addr,
+    /// Height the pause lifts at on its own, if any
+    pub expires_at_height: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarketplaceUnpaused {
+    pub unpaused_by: Addr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddressBlocked {
+    pub address: Addr,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddressUnblocked {
+    pub address: Addr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenBlocked {
+    pub contract_address: Addr,
+    pub token_id: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TokenUnblocked {
+    pub contract_address: Addr,
+    pub token_id: String,
+}
+
+impl MarketplaceEvent for ListingCreated {
+    const NAME: &'static str = "listing_created";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("token_id", &self.token_id)
+            .add_attribute("seller", &self.seller)
+            .add_attribute("price", self.price.amount)
+            .add_attribute("denom", &self.price.denom)
+            .add_attribute("is_escrowed", self.is_escrowed.to_string())
+            .add_attribute("version", self.version.to_string())
+            .add_attribute(
+                "starts_at",
+                optional(&self.starts_at.map(|starts_at| starts_at.nanos())),
+            )
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(ListingCreated {
+            contract_address: address(event, "contract_address")?,
+            token_id: attribute(event, "token_id")?,
+            seller: address(event, "seller")?,
+            price: coin(event, "price", "denom")?,
+            is_escrowed: parse(event, "is_escrowed")?,
+            version: parse(event, "version")?,
+            starts_at: parse_optional(event, "starts_at")?.map(Timestamp::from_nanos),
+        })
+    }
+}
+
+impl MarketplaceEvent for ListingUpdated {
+    const NAME: &'static str = "listing_updated";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("token_id", &self.token_id)
+            .add_attribute("seller", &self.seller)
+            .add_attribute("price", self.price.amount)
+            .add_attribute("denom", &self.price.denom)
+            .add_attribute("is_escrowed", self.is_escrowed.to_string())
+            .add_attribute("version", self.version.to_string())
+            .add_attribute(
+                "starts_at",
+                optional(&self.starts_at.map(|starts_at| starts_at.nanos())),
+            )
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(ListingUpdated {
+            contract_address: address(event, "contract_address")?,
+            token_id: attribute(event, "token_id")?,
+            seller: address(event, "seller")?,
+            price: coin(event, "price", "denom")?,
+            is_escrowed: parse(event, "is_escrowed")?,
+            version: parse(event, "version")?,
+            starts_at: parse_optional(event, "starts_at")?.map(Timestamp::from_nanos),
+        })
+    }
+}
+
+impl MarketplaceEvent for ListingCancelled {
+    const NAME: &'static str = "listing_cancelled";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("token_id", &self.token_id)
+            .add_attribute("seller", &self.seller)
+            .add_attribute("cancelled_by", &self.cancelled_by)
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(ListingCancelled {
+            contract_address: address(event, "contract_address")?,
+            token_id: attribute(event, "token_id")?,
+            seller: address(event, "seller")?,
+            cancelled_by: address(event, "cancelled_by")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for ListingSold {
+    const NAME: &'static str = "listing_sold";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("token_id", &self.token_id)
+            .add_attribute("seller", &self.seller)
+            .add_attribute("buyer", &self.buyer)
+            .add_attribute("price", self.price.amount)
+            .add_attribute("denom", &self.price.denom)
+            .add_attribute("taker_fee", self.taker_fee)
+            .add_attribute("royalty", self.royalty)
+            .add_attribute("royalty_recipient", optional(&self.royalty_recipient))
+            .add_attribute("source", self.source.as_str())
+            .add_attribute("nonce", optional(&self.nonce))
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(ListingSold {
+            contract_address: address(event, "contract_address")?,
+            token_id: attribute(event, "token_id")?,
+            seller: address(event, "seller")?,
+            buyer: address(event, "buyer")?,
+            price: coin(event, "price", "denom")?,
+            taker_fee: parse(event, "taker_fee")?,
+            royalty: parse(event, "royalty")?,
+            royalty_recipient: parse_optional::<String>(event, "royalty_recipient")?
+                .map(Addr::unchecked),
+            source: parse(event, "source")?,
+            nonce: parse_optional(event, "nonce")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for OfferCreated {
+    const NAME: &'static str = "offer_created";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("offeror", &self.offeror)
+            .add_attribute("price", self.price.amount)
+            .add_attribute("denom", &self.price.denom)
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(OfferCreated {
+            contract_address: address(event, "contract_address")?,
+            offeror: address(event, "offeror")?,
+            price: coin(event, "price", "denom")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for OfferCancelled {
+    const NAME: &'static str = "offer_cancelled";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("offeror", &self.offeror)
+            .add_attribute("price", self.price.amount)
+            .add_attribute("denom", &self.price.denom)
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(OfferCancelled {
+            contract_address: address(event, "contract_address")?,
+            offeror: address(event, "offeror")?,
+            price: coin(event, "price", "denom")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for OfferAccepted {
+    const NAME: &'static str = "offer_accepted";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("token_id", &self.token_id)
+            .add_attribute("offeror", &self.offeror)
+            .add_attribute("seller", &self.seller)
+            .add_attribute("price", self.price.amount)
+            .add_attribute("denom", &self.price.denom)
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(OfferAccepted {
+            contract_address: address(event, "contract_address")?,
+            token_id: attribute(event, "token_id")?,
+            offeror: address(event, "offeror")?,
+            seller: address(event, "seller")?,
+            price: coin(event, "price", "denom")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for ConfigUpdated {
+    const NAME: &'static str = "config_updated";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("updated_by", &self.updated_by)
+            .add_attribute("taker_fee", optional(&self.taker_fee))
+            .add_attribute("registration_bond", optional(&self.registration_bond))
+            .add_attribute("pull_payments", optional(&self.pull_payments))
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(ConfigUpdated {
+            updated_by: address(event, "updated_by")?,
+            taker_fee: parse_optional(event, "taker_fee")?,
+            registration_bond: parse_optional(event, "registration_bond")?,
+            pull_payments: parse_optional(event, "pull_payments")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for OwnershipUpdated {
+    const NAME: &'static str = "ownership_updated";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("owner", optional(&self.owner))
+            .add_attribute("pending_owner", optional(&self.pending_owner))
+            .add_attribute("pending_expiry", optional(&self.pending_expiry))
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(OwnershipUpdated {
+            owner: parse_optional::<String>(event, "owner")?.map(Addr::unchecked),
+            pending_owner: parse_optional::<String>(event, "pending_owner")?.map(Addr::unchecked),
+            pending_expiry: parse_optional(event, "pending_expiry")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for CollectionRegistered {
+    const NAME: &'static str = "collection_registered";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("royalty_percentage", optional(&self.royalty_percentage))
+            .add_attribute(
+                "royalty_payment_address",
+                optional(&self.royalty_payment_address),
+            )
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(CollectionRegistered {
+            contract_address: address(event, "contract_address")?,
+            royalty_percentage: parse_optional(event, "royalty_percentage")?,
+            royalty_payment_address: parse_optional::<String>(event, "royalty_payment_address")?
+                .map(Addr::unchecked),
+        })
+    }
+}
+
+impl MarketplaceEvent for CollectionPauseUpdated {
+    const NAME: &'static str = "collection_pause_updated";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("is_paused", self.is_paused.to_string())
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(CollectionPauseUpdated {
+            contract_address: address(event, "contract_address")?,
+            is_paused: parse(event, "is_paused")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for MarketplacePaused {
+    const NAME: &'static str = "marketplace_paused";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("paused_by", &self.paused_by)
+            .add_attribute("expires_at_height", optional(&self.expires_at_height))
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(MarketplacePaused {
+            paused_by: address(event, "paused_by")?,
+            expires_at_height: parse_optional(event, "expires_at_height")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for MarketplaceUnpaused {
+    const NAME: &'static str = "marketplace_unpaused";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME).add_attribute("unpaused_by", &self.unpaused_by)
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(MarketplaceUnpaused {
+            unpaused_by: address(event, "unpaused_by")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for AddressBlocked {
+    const NAME: &'static str = "address_blocked";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("address", &self.address)
+            .add_attribute("reason", &self.reason)
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(AddressBlocked {
+            address: address(event, "address")?,
+            reason: attribute(event, "reason")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for AddressUnblocked {
+    const NAME: &'static str = "address_unblocked";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME).add_attribute("address", &self.address)
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(AddressUnblocked {
+            address: address(event, "address")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for TokenBlocked {
+    const NAME: &'static str = "token_blocked";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("token_id", &self.token_id)
+            .add_attribute("reason", &self.reason)
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(TokenBlocked {
+            contract_address: address(event, "contract_address")?,
+            token_id: attribute(event, "token_id")?,
+            reason: attribute(event, "reason")?,
+        })
+    }
+}
+
+impl MarketplaceEvent for TokenUnblocked {
+    const NAME: &'static str = "token_unblocked";
+
+    fn to_event(&self) -> Event {
+        new_event(Self::NAME)
+            .add_attribute("contract_address", &self.contract_address)
+            .add_attribute("token_id", &self.token_id)
+    }
+
+    fn from_event(event: &Event) -> StdResult<Self> {
+        assert_event(event, Self::NAME)?;
+
+        Ok(TokenUnblocked {
+            contract_address: address(event, "contract_address")?,
+            token_id: attribute(event, "token_id")?,
+        })
+    }
+}
+
+fn new_event(name: &str) -> Event {
+    Event::new(name).add_attribute("schema_version", EVENT_SCHEMA_VERSION)
+}
+
+/// The event type without the `wasm-` prefix added on chain
+fn event_name(event: &Event) -> &str {
+    event.ty.strip_prefix("wasm-").unwrap_or(&event.ty)
+}
+
+fn assert_event(event: &Event, name: &str) -> StdResult<()> {
+    if event_name(event) != name {
+        return Err(StdError::parse_err(
+            name,
+            format!("unexpected event type {}", event.ty),
+        ));
+    }
+
+    let version = attribute(event, "schema_version")?;
+
+    if version != EVENT_SCHEMA_VERSION {
+        return Err(StdError::parse_err(
+            name,
+            format!("unsupported schema version {version}"),
+        ));
+    }
+
+    Ok(())
+}
+
+fn attribute(event: &Event, key: &str) -> StdResult<String> {
+    event
+        .attributes
+        .iter()
+        .find(|attribute| attribute.key == key)
+        .map(|attribute| attribute.value.clone())
+        .ok_or_else(|| StdError::not_found(format!("{} attribute {key}", event.ty)))
+}
+
+fn address(event: &Event, key: &str) -> StdResult<Addr> {
+    attribute(event, key).map(Addr::unchecked)
+}
+
+fn coin(event: &Event, amount_key: &str, denom_key: &str) -> StdResult<Coin> {
+    Ok(Coin {
+        amount: parse(event, amount_key)?,
+        denom: attribute(event, denom_key)?,
+    })
+}
+
+fn parse<T>(event: &Event, key: &str) -> StdResult<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = attribute(event, key)?;
+
+    value
+        .parse()
+        .map_err(|err| StdError::parse_err(key, format!("{value}: {err}")))
+}
+
+/// Reads an attribute written by `optional`, where `null` means `None`
+fn parse_optional<T>(event: &Event, key: &str) -> StdResult<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match attribute(event, key)?.as_str() {
+        "null" => Ok(None),
+        _ => parse(event, key).map(Some),
+    }
+}
+
+fn optional<T: ToString>(value: &Option<T>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "null".to_string(),
+    }
+}
@@ -1,27 +1,343 @@
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use cosmwasm_std::{to_binary, Addr, CosmosMsg, StdResult, WasmMsg};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Coin, CosmosMsg, QuerierWrapper, StdError, StdResult, Uint64, WasmMsg,
+};
 
-use crate::msg::ExecuteMsg;
+use crate::msg::{
+    BalancesInfo, CollectionsInfo, ConfigInfo, ExecuteMsg, LaunchpadInfo, ListedSale,
+    ListedSalesInfo, ListingStatus, MerkleProof, QueryMsg, RegisteredCollection,
+    RegisteredCollectionsInfo, SaleSimulationInfo, SalesInfo, SignedOrder, TakerFeeInfo,
+};
+use crate::state::{Collection, Sale};
 
-/// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
-/// for working with this.
+/// MarketplaceContract is a wrapper around Addr that builds marketplace messages with the
+/// funds they require and provides typed queries.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct CwTemplateContract(pub Addr);
+pub struct MarketplaceContract(pub Addr);
 
-impl CwTemplateContract {
+impl MarketplaceContract {
     pub fn addr(&self) -> Addr {
         self.0.clone()
     }
 
-    pub fn call<T: Into<ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
+    pub fn call<T: Into<ExecuteMsg>>(&self, msg: T, funds: Vec<Coin>) -> StdResult<CosmosMsg> {
         let msg = to_binary(&msg.into())?;
         Ok(WasmMsg::Execute {
             contract_addr: self.addr().into(),
             msg,
-            funds: vec![],
+            funds,
         }
         .into())
     }
+
+    /// Builds `msg` with the funds the marketplace expects for it. `Buy` attaches its expected
+    /// price, or else the current listing price, `SubmitCollection` the registration bond and
+    /// `Mint` the price of the launchpad's active phase. `ContributeToGroupBuy` and
+    /// `CommitSealedBid` are rejected as the amount is the caller's choice, use
+    /// `contribute_to_group_buy` and `commit_sealed_bid` instead.
+    pub fn execute(&self, querier: &QuerierWrapper, msg: ExecuteMsg) -> StdResult<CosmosMsg> {
+        let funds = match &msg {
+            ExecuteMsg::Buy {
+                expected_price: Some(price),
+                ..
+            } => vec![price.clone()],
+            ExecuteMsg::Buy {
+                contract_address,
+                token_id,
+                ..
+            } => vec![self.sale(querier, contract_address, token_id)?.price],
+            ExecuteMsg::CreateCollectionOffer { price, .. } => vec![price.clone()],
+            ExecuteMsg::FillSignedOrder { order, .. } => vec![order.price.clone()],
+            ExecuteMsg::SubmitCollection { .. } => {
+                let config = self.config(querier)?.config;
+                if config.registration_bond.is_zero() {
+                    vec![]
+                } else {
+                    vec![Coin::new(
+                        config.registration_bond.u128(),
+                        config.native_denom,
+                    )]
+                }
+            }
+            ExecuteMsg::Mint {
+                contract_address, ..
+            } => {
+                let price = self.mint_price(querier, contract_address)?;
+                if price.amount.is_zero() {
+                    vec![]
+                } else {
+                    vec![price]
+                }
+            }
+            ExecuteMsg::ContributeToGroupBuy { .. } => {
+                return Err(StdError::generic_err(
+                    "ContributeToGroupBuy needs an amount, use contribute_to_group_buy",
+                ))
+            }
+            ExecuteMsg::CommitSealedBid { .. } => {
+                return Err(StdError::generic_err(
+                    "CommitSealedBid needs a deposit, use commit_sealed_bid",
+                ))
+            }
+            _ => vec![],
+        };
+
+        self.call(msg, funds)
+    }
+
+    /// Buys a token at its current listing, failing if the listing changes before the
+    /// message executes.
+    pub fn buy(
+        &self,
+        querier: &QuerierWrapper,
+        contract_address: impl Into<String>,
+        token_id: impl Into<String>,
+    ) -> StdResult<CosmosMsg> {
+        let contract_address = contract_address.into();
+        let token_id = token_id.into();
+        let sale = self.sale(querier, &contract_address, &token_id)?;
+
+        self.call(
+            ExecuteMsg::Buy {
+                contract_address,
+                token_id,
+                expected_price: Some(sale.price.clone()),
+                expected_version: Some(sale.version),
+                merkle_proof: None,
+            },
+            vec![sale.price],
+        )
+    }
+
+    pub fn create_collection_offer(
+        &self,
+        contract_address: impl Into<String>,
+        price: Coin,
+    ) -> StdResult<CosmosMsg> {
+        self.call(
+            ExecuteMsg::CreateCollectionOffer {
+                contract_address: contract_address.into(),
+                price: price.clone(),
+            },
+            vec![price],
+        )
+    }
+
+    pub fn fill_signed_order(
+        &self,
+        order: SignedOrder,
+        pubkey: Binary,
+        signature: Binary,
+    ) -> StdResult<CosmosMsg> {
+        let funds = vec![order.price.clone()];
+        self.call(
+            ExecuteMsg::FillSignedOrder {
+                order,
+                pubkey,
+                signature,
+            },
+            funds,
+        )
+    }
+
+    /// Commits to a sealed bid, escrowing `deposit` as the most the bid can reveal
+    pub fn commit_sealed_bid(
+        &self,
+        contract_address: impl Into<String>,
+        token_id: impl Into<String>,
+        commitment: Binary,
+        deposit: Coin,
+    ) -> StdResult<CosmosMsg> {
+        self.call(
+            ExecuteMsg::CommitSealedBid {
+                contract_address: contract_address.into(),
+                token_id: token_id.into(),
+                commitment,
+            },
+            vec![deposit],
+        )
+    }
+
+    pub fn contribute_to_group_buy(&self, group_buy_id: u64, amount: Coin) -> StdResult<CosmosMsg> {
+        self.call(
+            ExecuteMsg::ContributeToGroupBuy { group_buy_id },
+            vec![amount],
+        )
+    }
+
+    pub fn submit_collection(
+        &self,
+        querier: &QuerierWrapper,
+        contract_address: impl Into<String>,
+        royalty_percentage: Option<u64>,
+        royalty_payment_address: Option<String>,
+    ) -> StdResult<CosmosMsg> {
+        self.execute(
+            querier,
+            ExecuteMsg::SubmitCollection {
+                contract_address: contract_address.into(),
+                royalty_percentage,
+                royalty_payment_address,
+            },
+        )
+    }
+
+    /// Mints the next launchpad token at the active phase's price
+    pub fn mint(
+        &self,
+        querier: &QuerierWrapper,
+        contract_address: impl Into<String>,
+        merkle_proof: Option<MerkleProof>,
+    ) -> StdResult<CosmosMsg> {
+        self.execute(
+            querier,
+            ExecuteMsg::Mint {
+                contract_address: contract_address.into(),
+                merkle_proof,
+            },
+        )
+    }
+
+    pub fn query<T: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper,
+        req: QueryMsg,
+    ) -> StdResult<T> {
+        querier.query_wasm_smart(self.addr(), &req)
+    }
+
+    pub fn sale(
+        &self,
+        querier: &QuerierWrapper,
+        contract_address: impl Into<String>,
+        token_id: impl Into<String>,
+    ) -> StdResult<Sale> {
+        let res: SalesInfo = self.query(
+            querier,
+            QueryMsg::GetSale {
+                contract_address: contract_address.into(),
+                token_id: token_id.into(),
+            },
+        )?;
+
+        res.sales
+            .into_iter()
+            .next()
+            .ok_or_else(|| StdError::not_found("Sale"))
+    }
+
+    pub fn sales(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+        status: Option<ListingStatus>,
+    ) -> StdResult<Vec<ListedSale>> {
+        let res: ListedSalesInfo = self.query(
+            querier,
+            QueryMsg::GetSales {
+                start_after,
+                limit,
+                status,
+            },
+        )?;
+        Ok(res.sales)
+    }
+
+    pub fn collection(
+        &self,
+        querier: &QuerierWrapper,
+        contract_address: impl Into<String>,
+    ) -> StdResult<Collection> {
+        let res: CollectionsInfo = self.query(
+            querier,
+            QueryMsg::GetCollection {
+                contract_address: contract_address.into(),
+            },
+        )?;
+
+        res.collections
+            .into_iter()
+            .next()
+            .ok_or_else(|| StdError::not_found("Collection"))
+    }
+
+    pub fn collections(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<RegisteredCollection>> {
+        let res: RegisteredCollectionsInfo =
+            self.query(querier, QueryMsg::GetCollections { start_after, limit })?;
+        Ok(res.collections)
+    }
+
+    pub fn taker_fee(&self, querier: &QuerierWrapper) -> StdResult<Uint64> {
+        let res: TakerFeeInfo = self.query(querier, QueryMsg::GetTakerFee {})?;
+        Ok(res.taker_fee)
+    }
+
+    pub fn config(&self, querier: &QuerierWrapper) -> StdResult<ConfigInfo> {
+        self.query(querier, QueryMsg::GetConfig {})
+    }
+
+    /// How a sale at `price` would be split between the taker, royalty recipient and seller
+    pub fn simulate_sale(
+        &self,
+        querier: &QuerierWrapper,
+        contract_address: impl Into<String>,
+        price: Coin,
+    ) -> StdResult<SaleSimulationInfo> {
+        self.query(
+            querier,
+            QueryMsg::SimulateSale {
+                contract_address: contract_address.into(),
+                price,
+            },
+        )
+    }
+
+    pub fn launchpad(
+        &self,
+        querier: &QuerierWrapper,
+        contract_address: impl Into<String>,
+    ) -> StdResult<LaunchpadInfo> {
+        self.query(
+            querier,
+            QueryMsg::GetLaunchpad {
+                contract_address: contract_address.into(),
+            },
+        )
+    }
+
+    /// Price of the launchpad phase open at the current block time
+    pub fn mint_price(
+        &self,
+        querier: &QuerierWrapper,
+        contract_address: impl Into<String>,
+    ) -> StdResult<Coin> {
+        let res = self.launchpad(querier, contract_address)?;
+
+        res.active_phase
+            .and_then(|index| res.launchpad.phases.into_iter().nth(index as usize))
+            .map(|phase| phase.price)
+            .ok_or_else(|| StdError::not_found("Active mint phase"))
+    }
+
+    pub fn balances(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+    ) -> StdResult<Vec<Coin>> {
+        let res: BalancesInfo = self.query(
+            querier,
+            QueryMsg::GetBalances {
+                address: address.into(),
+            },
+        )?;
+        Ok(res.balances)
+    }
 }
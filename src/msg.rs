@@ -1,14 +1,23 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Coin, Uint64};
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Coin, CosmosMsg, StdResult, Timestamp, Uint128, Uint64, WasmMsg,
+};
+use cw721::Cw721ReceiveMsg;
 use cw_ownable::cw_ownable_execute;
 
-use crate::state::{Collection, Sale, TokenId};
+use crate::state::{
+    BlockEntry, Collection, CollectionMetadata, Config, Deregistration, GroupBuy, Hook, Launchpad,
+    MerkleAllowlist, MintPhase, Offer, Pause, PendingCollection, Role, Sale, SealedAuction,
+    SealedAuctionTerms, SealedBid, TokenId, Trade,
+};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub taker_fee: Uint64,
     pub native_denom: String,
     pub taker_address: String,
+    pub registration_bond: Uint128,
+    pub pull_payments: bool,
 }
 
 #[cw_ownable_execute]
@@ -24,7 +33,7 @@ pub enum ExecuteMsg {
         contract_address: String,
         royalty_percentage: Option<u64>,
         royalty_payment_address: Option<String>,
-        is_paused: bool,
+        is_paused: Option<bool>,
     },
     AdminRemoveSales {
         contract_address: String,
@@ -33,24 +42,139 @@ pub enum ExecuteMsg {
     UpdateTakerFee {
         taker_fee: Uint64,
     },
+    UpdateRegistrationBond {
+        registration_bond: Uint128,
+    },
+    /// Switches between sending proceeds on sale and crediting them for `Withdraw`
+    UpdatePullPayments {
+        pull_payments: bool,
+    },
+    ApproveCollection {
+        contract_address: String,
+    },
+    RejectCollection {
+        contract_address: String,
+        reason: String,
+    },
+    UpdateCollectionPause {
+        contract_address: String,
+        is_paused: bool,
+    },
+    /// Stops trading in a collection and starts removing its listings, offers and auctions
+    DeregisterCollection {
+        contract_address: String,
+    },
+    /// Removes up to `limit` listings, offers and auction bids of a deregistered collection,
+    /// refunding offers and bids and returning escrowed tokens. Callable by anyone.
+    CleanupDeregisteredCollection {
+        contract_address: String,
+        limit: Option<u32>,
+    },
+    /// Adds and removes trusted cw721 code IDs. While none are set, any cw721 may register.
+    UpdateAllowedCodeIds {
+        add: Vec<u64>,
+        remove: Vec<u64>,
+    },
+    /// Sets the display metadata and verification badge of a registered collection
+    UpdateCollectionMetadata {
+        contract_address: String,
+        metadata: CollectionMetadata,
+    },
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    /// Halts listing, buying and offers across all collections
+    PauseMarketplace {
+        expires_at_height: Option<u64>,
+    },
+    UnpauseMarketplace {},
+    /// Subscribes a contract to `MarketplaceHookMsg` notifications
+    AddHook {
+        contract_address: String,
+        ignore_errors: bool,
+    },
+    RemoveHook {
+        contract_address: String,
+    },
+    // Moderator functions
+    BlockAddress {
+        address: String,
+        reason: String,
+    },
+    UnblockAddress {
+        address: String,
+    },
+    BlockToken {
+        contract_address: String,
+        token_id: TokenId,
+        reason: String,
+    },
+    UnblockToken {
+        contract_address: String,
+        token_id: TokenId,
+    },
+    // Collection creator functions
+    /// Submits a collection for curator review, escrowing the registration bond. The creator's
+    /// submission replaces a pending one from anyone else, whose bond is refunded.
+    SubmitCollection {
+        contract_address: String,
+        royalty_percentage: Option<u64>,
+        royalty_payment_address: Option<String>,
+    },
+    /// Updates the royalty of a registered collection, leaving omitted fields unchanged
+    UpdateCollectionRoyalty {
+        contract_address: String,
+        royalty_percentage: Option<u64>,
+        royalty_payment_address: Option<String>,
+    },
     // Seller / token owner functions
+    /// Lists a token or updates its listing. A listing with `starts_at` can only be bought from
+    /// that time on.
     UpdateSale {
         contract_address: String,
         token_id: TokenId,
         price: Coin,
+        starts_at: Option<Timestamp>,
     },
     RemoveSale {
         contract_address: String,
         token_id: TokenId,
     },
+    /// cw721 `SendNft` hook, the `msg` must be a `ReceiveNftMsg`
+    ReceiveNft(Cw721ReceiveMsg),
+    /// Invalidates signed orders with the given nonces
+    CancelOrderNonces {
+        nonces: Vec<u64>,
+    },
+    /// Invalidates every signed order with a nonce below `min_nonce`
+    IncreaseMinNonce {
+        min_nonce: u64,
+    },
     AcceptCollectionOffer {
         contract_address: String,
         token_id: TokenId,
+        offeror: String,
+    },
+    /// Ends a sealed-bid auction that has received no bids, returning the token
+    CancelSealedAuction {
+        contract_address: String,
+        token_id: TokenId,
     },
     // Buyer functions
+    /// Buys a listed token. The purchase fails if the listing no longer has the given
+    /// `expected_price` or `expected_version`.
     Buy {
         contract_address: String,
         token_id: TokenId,
+        expected_price: Option<Coin>,
+        expected_version: Option<u64>,
+        /// Required for a private sale
+        merkle_proof: Option<MerkleProof>,
     },
     CreateCollectionOffer {
         contract_address: String,
@@ -59,6 +183,220 @@ pub enum ExecuteMsg {
     RemoveCollectionOffer {
         contract_address: String,
     },
+    /// Buys a token listed off-chain. `pubkey` is the seller's compressed secp256k1 key and
+    /// `signature` signs the sha256 of the JSON encoded `OrderSignDoc`.
+    FillSignedOrder {
+        order: SignedOrder,
+        pubkey: Binary,
+        signature: Binary,
+    },
+    /// Commits to a sealed bid, escrowing at least the reserve price. `commitment` is the
+    /// sha256 of the JSON encoded `SealedBidCommitment`, the funds sent are the most the bid
+    /// can reveal.
+    CommitSealedBid {
+        contract_address: String,
+        token_id: TokenId,
+        commitment: Binary,
+    },
+    /// Discloses a committed bid during the reveal phase
+    RevealSealedBid {
+        contract_address: String,
+        token_id: TokenId,
+        amount: Uint128,
+        salt: String,
+    },
+    /// Awards an auction whose reveal phase has ended to the highest valid bid and refunds
+    /// every other bidder, less the penalty on unrevealed bids. When the token, seller or winner
+    /// is blocked, refunds every bid and returns the token instead. Callable by anyone.
+    SettleSealedAuction {
+        contract_address: String,
+        token_id: TokenId,
+    },
+    /// Opens a pool that buys the current version of a listing for `recipient` once its
+    /// contributions reach the listing price
+    CreateGroupBuy {
+        contract_address: String,
+        token_id: TokenId,
+        recipient: String,
+        deadline: Timestamp,
+    },
+    /// Adds the sent funds to a group buy, refunding any amount above the price
+    ContributeToGroupBuy {
+        group_buy_id: u64,
+    },
+    /// Buys the listing of a fully funded group buy. Callable by anyone.
+    SettleGroupBuy {
+        group_buy_id: u64,
+    },
+    /// Returns the sender's contribution once the group buy's listing is removed or changed,
+    /// or its deadline passes
+    WithdrawGroupBuyContribution {
+        group_buy_id: u64,
+    },
+    // Launchpad functions
+    /// Opens a primary sale, accepting the minter role the sender proposed transferring to
+    /// the marketplace
+    CreateLaunchpad {
+        contract_address: String,
+        terms: LaunchpadTerms,
+    },
+    /// Adds and removes addresses on the allowlist of a launchpad phase
+    UpdateLaunchpadAllowlist {
+        contract_address: String,
+        phase_id: u32,
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Mints the next launchpad token to the sender at the active phase's price. Phases with
+    /// a Merkle allowlist require `merkle_proof`.
+    Mint {
+        contract_address: String,
+        merkle_proof: Option<MerkleProof>,
+    },
+    /// Sets or clears the Merkle allowlist of a launchpad phase or a listing
+    UpdateMerkleAllowlist {
+        contract_address: String,
+        target: MerkleAllowlistTarget,
+        allowlist: Option<MerkleAllowlist>,
+    },
+    /// Ends the primary sale and proposes returning the minter role to the creator
+    CloseLaunchpad {
+        contract_address: String,
+    },
+    // Proceeds recipient functions
+    /// Claims the credited proceeds in `denom`
+    Withdraw {
+        denom: String,
+    },
+}
+
+/// Primary sale set up by `CreateLaunchpad`
+#[cw_serde]
+pub struct LaunchpadTerms {
+    /// Receives the mint proceeds after the taker fee, the creator if unset
+    pub payment_address: Option<String>,
+    pub phases: Vec<MintPhase>,
+    pub max_supply: u64,
+    pub starting_token_id: u64,
+    pub base_token_uri: Option<String>,
+}
+
+/// Where a Merkle allowlist applies
+#[cw_serde]
+pub enum MerkleAllowlistTarget {
+    /// A phase of the collection's launchpad
+    MintPhase { phase_id: u32 },
+    /// A listing, making it a private sale
+    Sale { token_id: TokenId },
+}
+
+/// Proof that the sender is on a Merkle allowlist
+#[cw_serde]
+pub struct MerkleProof {
+    /// The sender's allocation, for allowlists with allocations
+    pub allocation: Option<u32>,
+    /// Sibling hashes from the leaf up to the root
+    pub proof: Vec<Binary>,
+}
+
+/// What a sealed bid commits to, binding the amount to one bidder and auction
+#[cw_serde]
+pub struct SealedBidCommitment {
+    pub contract_address: String,
+    pub token_id: TokenId,
+    pub bidder: String,
+    pub amount: Uint128,
+    pub salt: String,
+}
+
+/// An off-chain listing signed by the token owner
+#[cw_serde]
+pub struct SignedOrder {
+    pub seller: String,
+    pub contract_address: String,
+    pub token_id: TokenId,
+    pub price: Coin,
+    pub expires_at: Timestamp,
+    pub nonce: u64,
+}
+
+/// What a seller signs, binding the order to one chain and marketplace
+#[cw_serde]
+pub struct OrderSignDoc {
+    pub chain_id: String,
+    pub marketplace: String,
+    pub order: SignedOrder,
+}
+
+/// Notification sent to every hook, wrapped in `MarketplaceHookExecuteMsg`
+#[cw_serde]
+pub enum MarketplaceHookMsg {
+    /// A token was listed or its listing updated
+    Listed {
+        contract_address: String,
+        token_id: TokenId,
+        seller: String,
+        price: Coin,
+    },
+    /// A listing was removed without being bought, including when the token sold or was
+    /// escrowed some other way
+    Delisted {
+        contract_address: String,
+        token_id: TokenId,
+        seller: String,
+    },
+    /// A listed, signed order or auctioned token was bought
+    Sold {
+        contract_address: String,
+        token_id: TokenId,
+        seller: String,
+        buyer: String,
+        price: Coin,
+    },
+    /// A seller sold a token to a collection offer
+    OfferAccepted {
+        contract_address: String,
+        token_id: TokenId,
+        seller: String,
+        offeror: String,
+        price: Coin,
+    },
+}
+
+impl MarketplaceHookMsg {
+    /// Serializes the message for a hook's execute entry point.
+    pub fn into_binary(self) -> StdResult<Binary> {
+        to_binary(&MarketplaceHookExecuteMsg::MarketplaceHook(self))
+    }
+
+    /// Creates a cosmos message delivering this notification to `contract_addr`.
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: self.into_binary()?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+/// The execute message a hook contract must accept
+#[cw_serde]
+pub enum MarketplaceHookExecuteMsg {
+    MarketplaceHook(MarketplaceHookMsg),
+}
+
+/// Message carried by a cw721 `SendNft` to the marketplace
+#[cw_serde]
+pub enum ReceiveNftMsg {
+    /// Lists the sent token, keeping it in escrow until it is sold or delisted
+    ListForSale {
+        price: Coin,
+        starts_at: Option<Timestamp>,
+    },
+    /// Auctions the sent token to sealed bids, keeping it in escrow until the auction is
+    /// settled or cancelled
+    StartSealedAuction { terms: SealedAuctionTerms },
 }
 
 #[cw_serde]
@@ -69,19 +407,146 @@ pub enum QueryMsg {
         contract_address: String,
         token_id: TokenId,
     },
-    #[returns(SalesInfo)]
-    GetSales { start: u64, limit: u64 },
+    /// Listings ordered by collection and token ID, starting after the
+    /// `(contract_address, token_id)` pair in `start_after`
+    #[returns(ListedSalesInfo)]
+    GetSales {
+        start_after: Option<(String, TokenId)>,
+        limit: Option<u32>,
+        status: Option<ListingStatus>,
+    },
     #[returns(CollectionsInfo)]
     GetCollection { contract_address: String },
-    #[returns(CollectionsInfo)]
-    GetCollections { start: u64, limit: u64 },
+    #[returns(RegisteredCollectionsInfo)]
+    GetCollections {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     #[returns(TakerFeeInfo)]
     GetTakerFee {},
+    #[returns(ConfigInfo)]
+    GetConfig {},
+    #[returns(RoleMembersInfo)]
+    GetRoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(RolesInfo)]
+    GetRoles { address: String },
+    #[returns(CollectionMetadataInfo)]
+    GetCollectionMetadata { contract_address: String },
+    #[returns(HooksInfo)]
+    GetHooks {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(AllowedCodeIdsInfo)]
+    GetAllowedCodeIds {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Collections with metadata, optionally only those in `category` or with the given
+    /// verification status
+    #[returns(CollectionsMetadataInfo)]
+    SearchCollections {
+        category: Option<String>,
+        is_verified: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(PendingCollectionsInfo)]
+    GetPendingCollections {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Cleanup progress of a deregistered collection
+    #[returns(DeregistrationInfo)]
+    GetDeregistration { contract_address: String },
+    #[returns(OffersInfo)]
+    GetCollectionOffers {
+        contract_address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(BlockedAddressesInfo)]
+    GetBlockedAddresses {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(BlockedTokensInfo)]
+    GetBlockedTokens {
+        contract_address: String,
+        start_after: Option<TokenId>,
+        limit: Option<u32>,
+    },
+    #[returns(OrderNonceInfo)]
+    GetOrderNonce { seller: String, nonce: u64 },
+    #[returns(BalancesInfo)]
+    GetBalances { address: String },
+    #[returns(LastTradeInfo)]
+    GetLastTrade {
+        contract_address: String,
+        token_id: TokenId,
+    },
+    /// How a sale of a token in the collection at `price` would be paid out
+    #[returns(SaleSimulationInfo)]
+    SimulateSale {
+        contract_address: String,
+        price: Coin,
+    },
+    #[returns(SealedAuctionInfo)]
+    GetSealedAuction {
+        contract_address: String,
+        token_id: TokenId,
+    },
+    #[returns(SealedBidInfo)]
+    GetSealedBid {
+        contract_address: String,
+        token_id: TokenId,
+        bidder: String,
+    },
+    /// Allocations `address` has consumed under a Merkle root
+    #[returns(MerkleClaimsInfo)]
+    GetMerkleClaims {
+        merkle_root: Binary,
+        address: String,
+    },
+    #[returns(GroupBuyInfo)]
+    GetGroupBuy { group_buy_id: u64 },
+    #[returns(LaunchpadInfo)]
+    GetLaunchpad { contract_address: String },
+    /// How many tokens `address` minted in a launchpad phase and whether it is allowlisted
+    #[returns(LaunchpadMintsInfo)]
+    GetLaunchpadMints {
+        contract_address: String,
+        phase_id: u32,
+        address: String,
+    },
+    #[returns(GroupBuyContributionInfo)]
+    GetGroupBuyContribution {
+        group_buy_id: u64,
+        contributor: String,
+    },
+}
+
+/// Filters listings by whether they can be bought at the current block time
+#[cw_serde]
+pub enum ListingStatus {
+    Live,
+    Upcoming,
+    All,
 }
 
 #[cw_serde]
 pub enum MigrateMsg {
-    Migrate {},
+    /// Migrates storage to the current contract version, optionally updating config values
+    Migrate {
+        taker_fee: Option<Uint64>,
+        taker_address: Option<String>,
+        registration_bond: Option<Uint128>,
+        pull_payments: Option<bool>,
+    },
 }
 
 #[cw_serde]
@@ -89,12 +554,191 @@ pub struct SalesInfo {
     pub sales: Vec<Sale>,
 }
 
+#[cw_serde]
+pub struct ListedSale {
+    pub contract_address: Addr,
+    pub token_id: TokenId,
+    pub sale: Sale,
+}
+
+#[cw_serde]
+pub struct ListedSalesInfo {
+    pub sales: Vec<ListedSale>,
+}
+
 #[cw_serde]
 pub struct CollectionsInfo {
     pub collections: Vec<Collection>,
 }
 
+#[cw_serde]
+pub struct RegisteredCollection {
+    pub contract_address: Addr,
+    pub collection: Collection,
+}
+
+#[cw_serde]
+pub struct RegisteredCollectionsInfo {
+    pub collections: Vec<RegisteredCollection>,
+}
+
 #[cw_serde]
 pub struct TakerFeeInfo {
     pub taker_fee: Uint64,
 }
+
+#[cw_serde]
+pub struct ConfigInfo {
+    pub config: Config,
+    pub is_paused: bool,
+    pub pause: Option<Pause>,
+}
+
+#[cw_serde]
+pub struct RoleMembersInfo {
+    pub members: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct RolesInfo {
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct OffersInfo {
+    pub offers: Vec<Offer>,
+}
+
+#[cw_serde]
+pub struct BlockedAddress {
+    pub address: Addr,
+    pub entry: BlockEntry,
+}
+
+#[cw_serde]
+pub struct BlockedAddressesInfo {
+    pub addresses: Vec<BlockedAddress>,
+}
+
+#[cw_serde]
+pub struct BlockedToken {
+    pub contract_address: Addr,
+    pub token_id: TokenId,
+    pub entry: BlockEntry,
+}
+
+#[cw_serde]
+pub struct BlockedTokensInfo {
+    pub tokens: Vec<BlockedToken>,
+}
+
+#[cw_serde]
+pub struct CollectionMetadataInfo {
+    pub contract_address: Addr,
+    pub collection: Collection,
+    pub metadata: CollectionMetadata,
+}
+
+#[cw_serde]
+pub struct CollectionsMetadataInfo {
+    pub collections: Vec<CollectionMetadataInfo>,
+}
+
+#[cw_serde]
+pub struct HookInfo {
+    pub contract_address: Addr,
+    pub hook: Hook,
+}
+
+#[cw_serde]
+pub struct HooksInfo {
+    pub hooks: Vec<HookInfo>,
+}
+
+#[cw_serde]
+pub struct AllowedCodeIdsInfo {
+    pub code_ids: Vec<u64>,
+}
+
+#[cw_serde]
+pub struct PendingCollectionInfo {
+    pub contract_address: Addr,
+    pub pending: PendingCollection,
+}
+
+#[cw_serde]
+pub struct PendingCollectionsInfo {
+    pub collections: Vec<PendingCollectionInfo>,
+}
+
+#[cw_serde]
+pub struct OrderNonceInfo {
+    pub min_nonce: u64,
+    /// Whether an order with this nonce was filled or cancelled
+    pub is_used: bool,
+}
+
+#[cw_serde]
+pub struct BalancesInfo {
+    pub balances: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct LastTradeInfo {
+    pub trade: Option<Trade>,
+}
+
+#[cw_serde]
+pub struct SealedAuctionInfo {
+    pub auction: SealedAuction,
+}
+
+#[cw_serde]
+pub struct SealedBidInfo {
+    pub bid: Option<SealedBid>,
+}
+
+#[cw_serde]
+pub struct GroupBuyInfo {
+    pub group_buy: GroupBuy,
+}
+
+#[cw_serde]
+pub struct GroupBuyContributionInfo {
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct LaunchpadInfo {
+    pub launchpad: Launchpad,
+    /// Index of the phase open at the current block time
+    pub active_phase: Option<u32>,
+}
+
+#[cw_serde]
+pub struct LaunchpadMintsInfo {
+    pub minted: u32,
+    pub is_allowlisted: bool,
+}
+
+#[cw_serde]
+pub struct MerkleClaimsInfo {
+    pub claimed: u32,
+}
+
+#[cw_serde]
+pub struct DeregistrationInfo {
+    pub deregistration: Option<Deregistration>,
+    pub remaining_sales: u64,
+    pub remaining_offers: u64,
+    pub remaining_auctions: u64,
+}
+
+#[cw_serde]
+pub struct SaleSimulationInfo {
+    pub price: Coin,
+    pub taker_fee: Uint128,
+    pub royalty: Uint128,
+    pub royalty_recipient: Option<Addr>,
+    pub seller_proceeds: Uint128,
+}
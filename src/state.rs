@@ -1,30 +1,350 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin};
+use cosmwasm_std::{Addr, Binary, Coin, Empty, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 
+#[cw_serde]
+pub struct Config {
+    pub taker_fee: u64,
+    pub taker_address: Addr,
+    pub native_denom: String,
+    /// Native amount escrowed by `SubmitCollection`, zero for free submissions
+    pub registration_bond: Uint128,
+    /// Credit sale proceeds to `BALANCES` for recipients to withdraw instead of sending them
+    pub pull_payments: bool,
+}
+
 #[cw_serde]
 pub struct Collection {
     pub royalty_percentage: Option<u64>,
     pub royalty_payment_address: Option<Addr>,
     pub is_paused: bool,
+    /// Royalty increase waiting out its timelock
+    #[serde(default)]
+    pub pending_royalty: Option<PendingRoyalty>,
+    /// Wasm code ID of the cw721 contract, detected at registration
+    #[serde(default)]
+    pub code_id: Option<u64>,
+    /// Name from the cw721 `ContractInfo`, detected at registration
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Set by `DeregisterCollection`, the collection is removed once its cleanup completes
+    #[serde(default)]
+    pub is_deregistered: bool,
+}
+
+impl Collection {
+    /// Promotes the pending royalty once its timelock has passed.
+    pub fn apply_pending_royalty(&mut self, now: Timestamp) {
+        if let Some(pending) = &self.pending_royalty {
+            if pending.effective_at <= now {
+                self.royalty_percentage = Some(pending.royalty_percentage);
+                if let Some(royalty_payment_address) = &pending.royalty_payment_address {
+                    self.royalty_payment_address = Some(royalty_payment_address.clone());
+                }
+                self.pending_royalty = None;
+            }
+        }
+    }
+}
+
+#[cw_serde]
+pub struct PendingRoyalty {
+    pub royalty_percentage: u64,
+    /// Recipient of a collection that had none, which turns the royalty on
+    #[serde(default)]
+    pub royalty_payment_address: Option<Addr>,
+    pub effective_at: Timestamp,
+}
+
+/// Curated display details of a registered collection
+#[cw_serde]
+pub struct CollectionMetadata {
+    pub name: String,
+    pub description: Option<String>,
+    pub image_uri: Option<String>,
+    pub external_links: Vec<String>,
+    pub category: Option<String>,
+    /// Set by curators once the collection is confirmed to be authentic
+    pub is_verified: bool,
+}
+
+/// A permissionless collection submission awaiting curator review
+#[cw_serde]
+pub struct PendingCollection {
+    pub submitter: Addr,
+    pub royalty_percentage: Option<u64>,
+    pub royalty_payment_address: Option<Addr>,
+    pub bond: Option<Coin>,
 }
 
 #[cw_serde]
 pub struct Sale {
     pub owner_address: Addr,
     pub price: Coin,
+    /// Whether the marketplace holds the token in custody instead of relying on an approval
+    #[serde(default)]
+    pub is_escrowed: bool,
+    /// Changes whenever the listing is created or updated, so buyers can pin the terms they saw
+    #[serde(default)]
+    pub version: u64,
+    /// When the listing becomes buyable, immediately if unset
+    #[serde(default)]
+    pub starts_at: Option<Timestamp>,
+    /// Makes the listing a private sale to the addresses on the allowlist
+    #[serde(default)]
+    pub merkle_allowlist: Option<MerkleAllowlist>,
+}
+
+impl Sale {
+    /// Whether the listing can be bought at `now`.
+    pub fn is_live(&self, now: Timestamp) -> bool {
+        self.starts_at.map_or(true, |starts_at| starts_at <= now)
+    }
 }
 
+#[cw_serde]
 pub struct Offer {
     pub offeror_address: Addr,
     pub price: Coin,
 }
 
+#[cw_serde]
+pub struct Pause {
+    pub paused_by: Addr,
+    /// Block height at which the pause lifts on its own
+    pub expires_at_height: Option<u64>,
+}
+
+/// Why and by whom an address or token was blocked
+#[cw_serde]
+pub struct BlockEntry {
+    pub reason: String,
+    pub blocked_by: Addr,
+    pub blocked_at: Timestamp,
+}
+
+/// Delegated admin roles, granted and revoked by the contract owner
+#[cw_serde]
+#[derive(Copy)]
+pub enum Role {
+    /// Can update fees and how proceeds are paid out
+    FeeAdmin,
+    /// Can register and update collections
+    CollectionCurator,
+    /// Can remove listings and pause collections
+    Moderator,
+    /// Can pause the marketplace
+    Pauser,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::FeeAdmin => "fee_admin",
+            Role::CollectionCurator => "collection_curator",
+            Role::Moderator => "moderator",
+            Role::Pauser => "pauser",
+        }
+    }
+}
+
+/// A contract notified of marketplace activity
+#[cw_serde]
+pub struct Hook {
+    /// Skip the hook when it fails instead of reverting the marketplace action
+    pub ignore_errors: bool,
+}
+
+/// What the winner of a sealed-bid auction pays
+#[cw_serde]
+#[derive(Copy)]
+pub enum AuctionPricing {
+    /// The winning bid
+    FirstPrice,
+    /// The second highest valid bid, or the reserve price without one
+    SecondPrice,
+}
+
+/// Terms a seller sets when starting a sealed-bid auction
+#[cw_serde]
+pub struct SealedAuctionTerms {
+    /// Lowest valid bid, in the native denom
+    pub reserve_price: Coin,
+    pub pricing: AuctionPricing,
+    /// When bids can first be committed, immediately if unset
+    pub starts_at: Option<Timestamp>,
+    /// End of the commit phase and start of the reveal phase
+    pub commit_ends_at: Timestamp,
+    /// End of the reveal phase, after which the auction can be settled
+    pub reveal_ends_at: Timestamp,
+    /// Percentage of an unrevealed bid's deposit forfeited to the seller
+    pub unrevealed_penalty_percentage: u64,
+}
+
+/// A token escrowed for a sealed-bid auction
+#[cw_serde]
+pub struct SealedAuction {
+    pub seller: Addr,
+    pub terms: SealedAuctionTerms,
+    pub bid_count: u32,
+    /// Highest valid bid revealed so far, the first to reveal wins a tie
+    pub highest_bid: Option<RevealedBid>,
+    /// Second highest valid amount revealed so far
+    pub second_highest_amount: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct RevealedBid {
+    pub bidder: Addr,
+    pub amount: Uint128,
+}
+
+/// A committed bid and the funds escrowed with it
+#[cw_serde]
+pub struct SealedBid {
+    /// sha256 of the JSON encoded `SealedBidCommitment`
+    pub commitment: Binary,
+    pub deposit: Coin,
+    /// Amount disclosed in the reveal phase, whether or not it was a valid bid
+    pub revealed_amount: Option<Uint128>,
+}
+
+/// Contributions pooled toward buying one listing for `recipient`
+#[cw_serde]
+pub struct GroupBuy {
+    pub contract_address: Addr,
+    pub token_id: TokenId,
+    /// Receives the token once the pool buys it, such as a DAO or multisig
+    pub recipient: Addr,
+    /// Price of the listing version the pool was created for
+    pub price: Coin,
+    pub listing_version: u64,
+    /// Contributions can be withdrawn from this time if the pool has not bought the token
+    pub deadline: Timestamp,
+    /// Sum of the contributions not withdrawn
+    pub total: Uint128,
+    pub is_settled: bool,
+}
+
+/// Root of a Merkle tree of allowlisted addresses. Leaves are the sha256 of the address, or
+/// of `{address}:{allocation}` with allocations, and pairs are hashed in sorted order.
+#[cw_serde]
+pub struct MerkleAllowlist {
+    pub root: Binary,
+    /// Leaves carry how many mints or purchases each address may make
+    pub with_allocations: bool,
+}
+
+/// A phase of a launchpad mint
+#[cw_serde]
+pub struct MintPhase {
+    pub name: String,
+    /// Mint price, zero for a free mint
+    pub price: Coin,
+    pub starts_at: Timestamp,
+    pub ends_at: Timestamp,
+    /// Most tokens one address can mint in the phase
+    pub per_wallet_limit: Option<u32>,
+    /// Most tokens minted in the phase, within the launchpad's supply
+    #[serde(default)]
+    pub max_supply: Option<u64>,
+    /// Restricts the phase to the addresses on its allowlist
+    pub is_allowlisted: bool,
+    /// Restricts the phase to addresses proving membership, in place of the stored allowlist
+    #[serde(default)]
+    pub merkle_allowlist: Option<MerkleAllowlist>,
+}
+
+/// Primary sale of a collection that delegated its minter role to the marketplace
+#[cw_serde]
+pub struct Launchpad {
+    pub creator: Addr,
+    /// Receives the mint proceeds after the taker fee
+    pub payment_address: Addr,
+    /// Phases in order, without overlap
+    pub phases: Vec<MintPhase>,
+    /// Most tokens the launchpad mints
+    pub max_supply: u64,
+    pub minted: u64,
+    /// Tokens minted in each phase, in the order of `phases`
+    #[serde(default)]
+    pub phase_minted: Vec<u64>,
+    /// Minted tokens are numbered up from this id
+    pub starting_token_id: u64,
+    /// Minted tokens get `{base_token_uri}/{token_id}` as their token URI
+    pub base_token_uri: Option<String>,
+}
+
+/// Progress of cleaning up a deregistered collection
+#[cw_serde]
+pub struct Deregistration {
+    pub deregistered_by: Addr,
+    pub deregistered_at: Timestamp,
+    pub sales_removed: u64,
+    pub offers_refunded: u64,
+    pub auctions_cancelled: u64,
+    /// Set when the last listing, offer and auction is removed
+    pub completed_at: Option<Timestamp>,
+}
+
+/// A settled token transfer awaiting its ownership check in `reply`
+#[cw_serde]
+pub struct PendingTransfer {
+    pub contract_address: Addr,
+    pub token_id: TokenId,
+    pub seller: Addr,
+    pub recipient: Addr,
+    pub price: Coin,
+}
+
+/// The last completed sale of a token
+#[cw_serde]
+pub struct Trade {
+    pub seller: Addr,
+    pub buyer: Addr,
+    pub price: Coin,
+    pub finalized_at: Timestamp,
+}
+
 pub type TokenId = String;
 
-pub const TAKERFEE: Item<u64> = Item::new("taker_fee");
-pub const TAKERADDRESS: Item<Addr> = Item::new("taker_address");
-pub const NATIVE_DENOM: Item<String> = Item::new("native_denom");
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const PAUSE: Item<Pause> = Item::new("pause");
 pub const COLLECTIONS: Map<Addr, Collection> = Map::new("collections");
+pub const PENDING_COLLECTIONS: Map<Addr, PendingCollection> = Map::new("pending_collections");
+pub const COLLECTION_METADATA: Map<Addr, CollectionMetadata> = Map::new("collection_metadata");
+pub const DEREGISTRATIONS: Map<Addr, Deregistration> = Map::new("deregistrations");
+/// Trusted cw721 code IDs, any code ID may register while this is empty
+pub const ALLOWED_CODE_IDS: Map<u64, Empty> = Map::new("allowed_code_ids");
 pub const SALES: Map<(Addr, TokenId), Sale> = Map::new("sales");
+/// Version given to the last saved listing
+pub const LISTING_VERSION: Item<u64> = Item::new("listing_version");
 pub const COLLECTION_OFFERS: Map<(Addr, Addr), Offer> = Map::new("collection_offers");
+pub const ROLES: Map<(&str, Addr), Empty> = Map::new("roles");
+pub const BLOCKED_ADDRESSES: Map<Addr, BlockEntry> = Map::new("blocked_addresses");
+pub const BLOCKED_TOKENS: Map<(Addr, TokenId), BlockEntry> = Map::new("blocked_tokens");
+/// Signed order nonces that were filled or cancelled, keyed by (seller, nonce)
+pub const USED_NONCES: Map<(Addr, u64), Empty> = Map::new("used_nonces");
+/// Lowest signed order nonce each seller still accepts
+pub const MIN_NONCES: Map<Addr, u64> = Map::new("min_nonces");
+/// Id of the next settled transfer, used as its reply id
+pub const NEXT_TRANSFER_ID: Item<u64> = Item::new("next_transfer_id");
+pub const PENDING_TRANSFERS: Map<u64, PendingTransfer> = Map::new("pending_transfers");
+pub const TRADES: Map<(Addr, TokenId), Trade> = Map::new("trades");
+pub const SEALED_AUCTIONS: Map<(Addr, TokenId), SealedAuction> = Map::new("sealed_auctions");
+/// Sealed bids keyed by (collection, token id, bidder)
+pub const SEALED_BIDS: Map<(Addr, TokenId, Addr), SealedBid> = Map::new("sealed_bids");
+pub const NEXT_GROUP_BUY_ID: Item<u64> = Item::new("next_group_buy_id");
+pub const GROUP_BUYS: Map<u64, GroupBuy> = Map::new("group_buys");
+/// Group buy contributions keyed by (group buy id, contributor)
+pub const GROUP_BUY_CONTRIBUTIONS: Map<(u64, Addr), Uint128> = Map::new("group_buy_contributions");
+pub const LAUNCHPADS: Map<Addr, Launchpad> = Map::new("launchpads");
+/// Launchpad allowlists keyed by (collection, phase index, address)
+pub const LAUNCHPAD_ALLOWLISTS: Map<(Addr, u32, Addr), Empty> = Map::new("launchpad_allowlists");
+/// Tokens minted keyed by (collection, phase index, address)
+pub const LAUNCHPAD_MINTS: Map<(Addr, u32, Addr), u32> = Map::new("launchpad_mints");
+/// Allocations consumed keyed by (Merkle root, address)
+pub const MERKLE_CLAIMS: Map<(&[u8], Addr), u32> = Map::new("merkle_claims");
+pub const HOOKS: Map<Addr, Hook> = Map::new("hooks");
+/// Withdrawable proceeds, keyed by (recipient, denom)
+pub const BALANCES: Map<(Addr, &str), Uint128> = Map::new("balances");